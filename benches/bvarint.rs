@@ -0,0 +1,236 @@
+use bvarint::{
+    decode_bvarint, decode_bvarint_unchecked, read_bvarint, read_bvarint_group4,
+    read_bvarint_with_buf, write_bvarint, write_bvarint_group4,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+const COUNT: usize = 4096;
+
+/// Deterministic pseudo-random values (xorshift) shifted to cover `bits`.
+fn values(bits: u32) -> Vec<u64> {
+    let mut x = 0x2545f4914f6cdd1du64;
+    (0..COUNT)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x >> (64 - bits)
+        })
+        .collect()
+}
+
+/// Values mixing all widths, weighted towards small ones.
+fn mixed_values() -> Vec<u64> {
+    let widths = values(6);
+    values(64)
+        .into_iter()
+        .zip(widths)
+        .map(|(v, w)| v >> (w % 64))
+        .collect()
+}
+
+fn encode(values: &[u64]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for &v in values {
+        write_bvarint(v, &mut buf).unwrap();
+    }
+    buf
+}
+
+fn distributions() -> Vec<(&'static str, Vec<u64>)> {
+    vec![
+        ("1-byte", values(64).into_iter().map(|v| v % 0xf7).collect()),
+        ("3-byte", values(16)),
+        ("5-byte", values(32)),
+        ("9-byte", values(64)),
+        ("mixed", mixed_values()),
+    ]
+}
+
+fn bench_write(c: &mut Criterion) {
+    let mut group = c.benchmark_group("write_bvarint");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let mut buf = Vec::with_capacity(COUNT * 9);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                buf.clear();
+                for &v in &values {
+                    write_bvarint(black_box(v), &mut buf).unwrap();
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_bvarint");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let buf = encode(&values);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut r = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    sum = sum.wrapping_add(read_bvarint(&mut r).unwrap());
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_read_with_buf(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_bvarint_with_buf");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let buf = encode(&values);
+        group.bench_function(name, |b| {
+            let mut scratch = [0; 8];
+            b.iter(|| {
+                let mut r = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    let v = read_bvarint_with_buf(&mut r, &mut scratch).unwrap();
+                    sum = sum.wrapping_add(v);
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_bvarint");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let buf = encode(&values);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    let (v, len) = decode_bvarint(rest).unwrap();
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Encoded length for each lead byte, 0 for the reserved `0xff`.
+const LEN_TABLE: [u8; 256] = {
+    let mut table = [1; 256];
+    let mut lead = 0xf7;
+    while lead < 0xff {
+        table[lead] = (lead - 0xf7 + 2) as u8;
+        lead += 1;
+    }
+    table[0xff] = 0;
+    table
+};
+
+/// `decode_bvarint` with a table lookup instead of a `match` on the lead
+/// byte, to compare the two dispatch strategies.
+fn decode_with_table(buf: &[u8]) -> Option<(u64, usize)> {
+    let lead = *buf.first()?;
+    let len = LEN_TABLE[lead as usize] as usize;
+    if len == 1 {
+        return Some((lead as u64, 1));
+    }
+    let tail = buf.get(1..len)?;
+    let mut a = [0; 8];
+    a[(9 - len)..].copy_from_slice(tail);
+    Some((u64::from_be_bytes(a), len))
+}
+
+fn bench_lead_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lead_dispatch");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let buf = encode(&values);
+        group.bench_function(format!("match/{}", name), |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    let (v, len) = decode_bvarint(rest).unwrap();
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+        group.bench_function(format!("table/{}", name), |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    let (v, len) = decode_with_table(rest).unwrap();
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_decode_unchecked(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode_bvarint_unchecked");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let buf = encode(&values);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                for _ in 0..COUNT {
+                    // SAFETY: `buf` holds COUNT values written by write_bvarint.
+                    let (v, len) = unsafe { decode_bvarint_unchecked(rest) };
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Compare with `read_bvarint`, which reads the same values one at a time.
+fn bench_read_group4(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_bvarint_group4");
+    group.throughput(Throughput::Elements(COUNT as u64));
+    for (name, values) in distributions() {
+        let mut buf = Vec::new();
+        write_bvarint_group4(&values, &mut buf).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let values = read_bvarint_group4(black_box(&buf[..]), COUNT).unwrap();
+                values.iter().fold(0u64, |sum, &v| sum.wrapping_add(v))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_write,
+    bench_read,
+    bench_read_with_buf,
+    bench_decode,
+    bench_decode_unchecked,
+    bench_lead_dispatch,
+    bench_read_group4
+);
+criterion_main!(benches);
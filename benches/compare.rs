@@ -0,0 +1,179 @@
+//! bvarint against LEB128 and fixed 8-byte big-endian, on the same values.
+//!
+//! The encoded sizes are printed before the timings.
+
+use bvarint::{decode_bvarint, BvarintVecExt};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::convert::TryInto;
+
+const COUNT: usize = 4096;
+
+/// Deterministic pseudo-random values (xorshift).
+fn random() -> Vec<u64> {
+    let mut x = 0x2545f4914f6cdd1du64;
+    (0..COUNT)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        })
+        .collect()
+}
+
+fn distributions() -> Vec<(&'static str, Vec<u64>)> {
+    let mut sorted: Vec<u64> = random().into_iter().map(|v| v >> 24).collect();
+    sorted.sort_unstable();
+    let deltas = sorted
+        .iter()
+        .scan(0, |prev, &v| Some(v - std::mem::replace(prev, v)))
+        .collect();
+    vec![
+        (
+            "uniform-small",
+            random().into_iter().map(|v| v % 256).collect(),
+        ),
+        ("uniform-full", random()),
+        // P(v >= k) is about 1/k, like a Zipf distribution over ranks.
+        (
+            "zipf",
+            random()
+                .into_iter()
+                .map(|v| u64::MAX / v.max(1) - 1)
+                .collect(),
+        ),
+        // Gaps between sorted 40-bit values, such as timestamps.
+        ("sorted-deltas", deltas),
+    ]
+}
+
+/// A simple unsigned LEB128 encoder, for reference.
+fn push_leb128(buf: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        buf.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// Decode a LEB128 value written by `push_leb128` from the front of `buf`.
+fn decode_leb128(buf: &[u8]) -> (u64, usize) {
+    let mut v = 0;
+    for (i, &b) in buf.iter().enumerate() {
+        v |= ((b & 0x7f) as u64) << (7 * i);
+        if b < 0x80 {
+            return (v, i + 1);
+        }
+    }
+    panic!("truncated LEB128 value");
+}
+
+fn encode_bvarint(values: &[u64], buf: &mut Vec<u8>) {
+    for &v in values {
+        buf.push_bvarint(v);
+    }
+}
+
+fn encode_leb128(values: &[u64], buf: &mut Vec<u8>) {
+    for &v in values {
+        push_leb128(buf, v);
+    }
+}
+
+fn encode_fixed(values: &[u64], buf: &mut Vec<u8>) {
+    for &v in values {
+        buf.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+fn encoded(values: &[u64], encode: fn(&[u64], &mut Vec<u8>)) -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode(values, &mut buf);
+    buf
+}
+
+fn print_sizes(_: &mut Criterion) {
+    println!(
+        "{:<16}{:>10}{:>10}{:>10}  (bytes for {} values)",
+        "distribution", "bvarint", "leb128", "fixed", COUNT
+    );
+    for (name, values) in distributions() {
+        println!(
+            "{:<16}{:>10}{:>10}{:>10}",
+            name,
+            encoded(&values, encode_bvarint).len(),
+            encoded(&values, encode_leb128).len(),
+            encoded(&values, encode_fixed).len(),
+        );
+    }
+}
+
+fn bench_encode(c: &mut Criterion) {
+    for (name, values) in distributions() {
+        let mut group = c.benchmark_group(format!("encode/{}", name));
+        group.throughput(Throughput::Elements(COUNT as u64));
+        let mut buf = Vec::with_capacity(COUNT * 10);
+        for (format, encode) in [
+            ("bvarint", encode_bvarint as fn(&[u64], &mut Vec<u8>)),
+            ("leb128", encode_leb128),
+            ("fixed", encode_fixed),
+        ] {
+            group.bench_function(format, |b| {
+                b.iter(|| {
+                    buf.clear();
+                    encode(black_box(&values), &mut buf);
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+fn bench_decode(c: &mut Criterion) {
+    for (name, values) in distributions() {
+        let mut group = c.benchmark_group(format!("decode/{}", name));
+        group.throughput(Throughput::Elements(COUNT as u64));
+
+        let buf = encoded(&values, encode_bvarint);
+        group.bench_function("bvarint", |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                while !rest.is_empty() {
+                    let (v, len) = decode_bvarint(rest).unwrap();
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+
+        let buf = encoded(&values, encode_leb128);
+        group.bench_function("leb128", |b| {
+            b.iter(|| {
+                let mut rest = black_box(&buf[..]);
+                let mut sum = 0u64;
+                while !rest.is_empty() {
+                    let (v, len) = decode_leb128(rest);
+                    sum = sum.wrapping_add(v);
+                    rest = &rest[len..];
+                }
+                sum
+            })
+        });
+
+        let buf = encoded(&values, encode_fixed);
+        group.bench_function("fixed", |b| {
+            b.iter(|| {
+                black_box(&buf[..])
+                    .chunks_exact(8)
+                    .map(|c| u64::from_be_bytes(c.try_into().unwrap()))
+                    .fold(0u64, u64::wrapping_add)
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, print_sizes, bench_encode, bench_decode);
+criterion_main!(benches);
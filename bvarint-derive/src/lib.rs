@@ -0,0 +1,138 @@
+//! Derive macros for `bvarint::BvarintEncode` and `bvarint::BvarintDecode`.
+//!
+//! Use these through the `derive` feature of `bvarint`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Index};
+
+/// How a field is encoded.
+enum Kind {
+    /// Through the field type's `BvarintEncode` / `BvarintDecode` impls.
+    Plain,
+    /// As `i64` using zigzag, from `#[bvarint(signed)]`.
+    Signed,
+}
+
+fn field_kind(field: &Field) -> syn::Result<Kind> {
+    let mut kind = Kind::Plain;
+    for attr in &field.attrs {
+        if !attr.path().is_ident("bvarint") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("signed") {
+                kind = Kind::Signed;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported bvarint attribute"))
+            }
+        })?;
+    }
+    Ok(kind)
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "bvarint derives only support structs",
+        )),
+    }
+}
+
+/// Derive `bvarint::BvarintEncode`, writing fields in declaration order.
+#[proc_macro_derive(BvarintEncode, attributes(bvarint))]
+pub fn derive_encode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_encode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_encode(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let mut writes = Vec::new();
+    for (i, field) in struct_fields(input)?.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(i);
+                quote!(#index)
+            }
+        };
+        writes.push(match field_kind(field)? {
+            Kind::Plain => quote! {
+                ::bvarint::BvarintEncode::write_bvarint(&self.#member, &mut w)?;
+            },
+            Kind::Signed => quote! {
+                ::bvarint::write_bvarint_i64(::std::convert::From::from(self.#member), &mut w)?;
+            },
+        });
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bvarint::BvarintEncode for #name #ty_generics #where_clause {
+            fn write_bvarint<W: ::std::io::Write>(&self, mut w: W) -> ::std::io::Result<()> {
+                #(#writes)*
+                Ok(())
+            }
+        }
+    })
+}
+
+/// Derive `bvarint::BvarintDecode`, reading fields in declaration order.
+#[proc_macro_derive(BvarintDecode, attributes(bvarint))]
+pub fn derive_decode(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_decode(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_decode(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = struct_fields(input)?;
+    let mut reads = Vec::new();
+    let mut vars = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let var = format_ident!("field{}", i);
+        let ty = &field.ty;
+        reads.push(match field_kind(field)? {
+            Kind::Plain => quote! {
+                let #var = <#ty as ::bvarint::BvarintDecode>::read_bvarint(&mut r)?;
+            },
+            Kind::Signed => quote! {
+                let #var: #ty = ::std::convert::TryFrom::try_from(
+                    ::bvarint::read_bvarint_i64(&mut r)?,
+                )
+                .map_err(|_| {
+                    ::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidData,
+                        concat!("out of range for ", stringify!(#ty)),
+                    )
+                })?;
+            },
+        });
+        vars.push(var);
+    }
+    let construct = match fields {
+        Fields::Named(named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote!(Self { #(#names: #vars),* })
+        }
+        Fields::Unnamed(_) => quote!(Self(#(#vars),*)),
+        Fields::Unit => quote!(Self),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::bvarint::BvarintDecode for #name #ty_generics #where_clause {
+            fn read_bvarint<R: ::std::io::Read>(mut r: R) -> ::std::io::Result<Self> {
+                #(#reads)*
+                Ok(#construct)
+            }
+        }
+    })
+}
@@ -0,0 +1,22 @@
+#![no_main]
+
+use bvarint::{decode_bvarint, read_bvarint, validate_bvarint, write_bvarint};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let from_reader = read_bvarint(data).ok();
+    let from_slice = decode_bvarint(data).ok();
+    assert_eq!(from_reader, from_slice.map(|(v, _)| v));
+
+    if let Some((v, len)) = from_slice {
+        let mut buf = Vec::new();
+        write_bvarint(v, &mut buf).unwrap();
+        // Canonical encodings re-encode to the same bytes. Overlong ones
+        // re-encode to something shorter.
+        if validate_bvarint(data).is_ok() {
+            assert_eq!(&data[..len], &buf[..]);
+        } else {
+            assert!(buf.len() < len);
+        }
+    }
+});
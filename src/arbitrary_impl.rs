@@ -0,0 +1,54 @@
+//! `arbitrary` support for [`Bvarint`] and [`EncodedU64`], for
+//! structure-aware fuzzing.
+//!
+//! Both are built from an arbitrary `u64`, so [`EncodedU64`] always holds a
+//! canonical encoding.
+
+use crate::Bvarint;
+use crate::EncodedU64;
+use arbitrary::Arbitrary;
+use arbitrary::Result;
+use arbitrary::Unstructured;
+
+impl<'a> Arbitrary<'a> for Bvarint {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Bvarint(u64::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u64::size_hint(depth)
+    }
+}
+
+impl<'a> Arbitrary<'a> for EncodedU64 {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(EncodedU64::new(u64::arbitrary(u)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u64::size_hint(depth)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::validate_bvarint;
+
+    #[test]
+    fn test_arbitrary() {
+        let data: Vec<u8> = (0..=255).collect();
+        let mut u = Unstructured::new(&data);
+        for _ in 0..20 {
+            let encoded = EncodedU64::arbitrary(&mut u).unwrap();
+            let bytes = encoded.as_bytes();
+            assert_eq!(validate_bvarint(bytes).unwrap(), bytes.len());
+            assert_eq!(EncodedU64::new(encoded.value()), encoded);
+        }
+
+        let data = [0x12, 0x34, 0x56, 0x78, 0, 0, 0, 0];
+        let v = Bvarint::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        let encoded = EncodedU64::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert_eq!(encoded.value(), v.0);
+    }
+}
@@ -0,0 +1,64 @@
+//! `tokio` `AsyncRead` / `AsyncWrite` support.
+
+use crate::encode_bvarint;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use std::io;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+
+/// Encode `v` and write it to `w`.
+pub async fn write_bvarint_async(v: u64, w: &mut (impl AsyncWrite + Unpin)) -> io::Result<()> {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_bvarint(v, &mut buf).expect("9 bytes fit any u64");
+    w.write_all(&buf[..len]).await
+}
+
+/// Read from `r` and return the decoded integer.
+///
+/// The lead byte is read first, then exactly the remaining width, so partial
+/// reads across await points are handled by `read_exact`.
+pub async fn read_bvarint_async(r: &mut (impl AsyncRead + Unpin)) -> io::Result<u64> {
+    let mut a = [0; 8];
+    let lead = r.read_u8().await?;
+    match lead {
+        0..=0xf6 => Ok(lead as _),
+        0xf7..=0xfe => {
+            let width = (lead - 0xf7 + 1) as usize;
+            r.read_exact(&mut a[(8 - width)..8]).await?;
+            Ok(u64::from_be_bytes(a))
+        }
+        0xff => Err(DecodeError::ReservedLeadByte { offset: 0 }.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+
+    #[tokio::test]
+    async fn test_duplex_round_trip() {
+        // A tiny buffer forces values to be split across reads.
+        let (mut w, mut r) = tokio::io::duplex(3);
+        let writer = tokio::spawn(async move {
+            for x in interesting_values() {
+                write_bvarint_async(x, &mut w).await.unwrap();
+            }
+        });
+        for x in interesting_values() {
+            assert_eq!(read_bvarint_async(&mut r).await.unwrap(), x);
+        }
+        writer.await.unwrap();
+        let err = read_bvarint_async(&mut r).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[tokio::test]
+    async fn test_reserved() {
+        let err = read_bvarint_async(&mut &[0xff][..]).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
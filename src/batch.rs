@@ -0,0 +1,703 @@
+//! Encoding and decoding many values at once.
+
+use crate::encode_all_to_vec;
+use crate::encode_bvarint;
+use crate::encoded_len;
+use crate::error::IndexedError;
+use crate::read_bvarint;
+use crate::read_bvarint_opt;
+use crate::stream::read_lead;
+use crate::write_bvarint;
+use crate::BvarintVecExt;
+use crate::MAX_ENCODED_LEN;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io;
+
+/// Encode `values` back-to-back and write them to `w` in one `write_all`.
+pub fn write_bvarint_slice(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    w.write_all(&encode_all_to_vec(values))
+}
+
+/// The most values reserved up front for a count that may come from the
+/// input. Past that, the `Vec` grows with the values actually read, so a
+/// corrupt count fails at EOF instead of allocating.
+pub(crate) const MAX_PREALLOC: usize = 4096;
+
+/// Read exactly `count` values from `r`.
+///
+/// At most 4096 values are reserved up front, so a corrupt `count` fails
+/// at EOF rather than allocating for it.
+pub fn read_bvarint_vec(mut r: impl io::Read, count: usize) -> io::Result<Vec<u64>> {
+    let mut values = Vec::with_capacity(count.min(MAX_PREALLOC));
+    for _ in 0..count {
+        values.push(read_bvarint(&mut r)?);
+    }
+    Ok(values)
+}
+
+/// Read `expected` values from `r`, and check that `r` ends right after
+/// them.
+///
+/// EOF before the last value is `UnexpectedEof`. Any byte after it is
+/// `InvalidData`, since the declared count disagrees with the payload. To
+/// check that, one more byte is read from `r`. Memory grows with the values
+/// read, not with `expected`, so a corrupt count cannot force a large
+/// allocation up front.
+pub fn read_bvarint_exact_count(mut r: impl io::Read, expected: usize) -> io::Result<Vec<u64>> {
+    let mut values = Vec::with_capacity(expected.min(MAX_PREALLOC));
+    for i in 0..expected {
+        let v = read_bvarint(&mut r).map_err(|e| IndexedError::wrap(e, i, expected))?;
+        values.push(v);
+    }
+    if read_lead(&mut r)?.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("trailing bytes after {} values", expected),
+        ));
+    }
+    Ok(values)
+}
+
+/// Read exactly `N` values from `r` into an array, without allocating.
+///
+/// If a value cannot be read, the error keeps its kind and reason, and its
+/// message says which index failed.
+pub fn read_bvarint_array<const N: usize>(mut r: impl io::Read) -> io::Result<[u64; N]> {
+    let mut values = [0; N];
+    for (i, v) in values.iter_mut().enumerate() {
+        *v = read_bvarint(&mut r).map_err(|e| IndexedError::wrap(e, i, N))?;
+    }
+    Ok(values)
+}
+
+/// Write the number of `values`, then the values, to `w` in one
+/// `write_all`.
+pub fn write_bvarint_column(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(values.len() + 1);
+    buf.push_bvarint(values.len() as u64);
+    for &v in values {
+        buf.push_bvarint(v);
+    }
+    w.write_all(&buf)
+}
+
+/// Read a column written by [`write_bvarint_column`] from `r`.
+///
+/// The stored count comes from the input, so at most 4096 values are
+/// reserved up front, and the `Vec` grows as values are read. A huge count
+/// with a short payload fails with `UnexpectedEof`. Failing to allocate is
+/// reported as `OutOfMemory` instead of aborting, as is a count above
+/// `usize::MAX`.
+pub fn read_bvarint_column(mut r: impl io::Read) -> io::Result<Vec<u64>> {
+    let count = read_bvarint(&mut r)?;
+    let count = usize::try_from(count)
+        .map_err(|_| io::Error::new(io::ErrorKind::OutOfMemory, "count exceeds usize::MAX"))?;
+    let mut values = Vec::new();
+    values
+        .try_reserve(count.min(MAX_PREALLOC))
+        .map_err(|e| io::Error::new(io::ErrorKind::OutOfMemory, e))?;
+    for _ in 0..count {
+        values.push(read_bvarint(&mut r)?);
+    }
+    Ok(values)
+}
+
+/// Mode flags of [`write_bvarint_block_adaptive`].
+const BLOCK_BVARINT: u8 = 0;
+const BLOCK_RAW: u8 = 1;
+
+/// Write a mode byte, then `values` either back-to-back as bvarints or as
+/// raw big-endian `u64`s, whichever is smaller, in one `write_all`.
+///
+/// Large random values take 9 bytes each as bvarints, so the raw form caps
+/// a block at 8 bytes per value plus the mode byte. Ties use bvarints.
+pub fn write_bvarint_block_adaptive(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    let packed_len: usize = values.iter().map(|&v| encoded_len(v)).sum();
+    let raw = packed_len > 8 * values.len();
+    let mut buf = Vec::with_capacity(1 + packed_len.min(8 * values.len()));
+    if raw {
+        buf.push(BLOCK_RAW);
+        for &v in values {
+            buf.extend_from_slice(&v.to_be_bytes());
+        }
+    } else {
+        buf.push(BLOCK_BVARINT);
+        for &v in values {
+            buf.push_bvarint(v);
+        }
+    }
+    w.write_all(&buf)
+}
+
+/// Read `count` values written by [`write_bvarint_block_adaptive`] from
+/// `r`.
+///
+/// An unknown mode byte is `InvalidData`.
+pub fn read_bvarint_block_adaptive(mut r: impl io::Read, count: usize) -> io::Result<Vec<u64>> {
+    let mut mode = [0];
+    r.read_exact(&mut mode)?;
+    match mode[0] {
+        BLOCK_BVARINT => read_bvarint_vec(r, count),
+        BLOCK_RAW => {
+            let mut values = Vec::with_capacity(count.min(MAX_PREALLOC));
+            for _ in 0..count {
+                let mut b = [0; 8];
+                r.read_exact(&mut b)?;
+                values.push(u64::from_be_bytes(b));
+            }
+            Ok(values)
+        }
+        m => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown block mode {}", m),
+        )),
+    }
+}
+
+/// Encode `values` back-to-back and write them to `w` in 4 KiB chunks.
+///
+/// This gives good throughput even if `w` is unbuffered, without holding
+/// all encodings in memory. Return the number of bytes written.
+pub fn encode_many(values: impl Iterator<Item = u64>, mut w: impl io::Write) -> io::Result<u64> {
+    const CHUNK: usize = 4096;
+    let mut buf = [0; CHUNK];
+    let mut len = 0;
+    let mut total = 0;
+    for v in values {
+        if len + MAX_ENCODED_LEN > CHUNK {
+            w.write_all(&buf[..len])?;
+            total += len as u64;
+            len = 0;
+        }
+        len += encode_bvarint(v, &mut buf[len..]).expect("9 bytes fit any u64");
+    }
+    w.write_all(&buf[..len])?;
+    Ok(total + len as u64)
+}
+
+/// Encode `a` then `b` and write them to `w` in one `write_all`.
+pub fn write_bvarint_pair(a: u64, b: u64, mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = [0; MAX_ENCODED_LEN * 2];
+    let len = encode_bvarint(a, &mut buf).expect("9 bytes fit any u64");
+    let len = len + encode_bvarint(b, &mut buf[len..]).expect("9 bytes fit any u64");
+    w.write_all(&buf[..len])
+}
+
+/// Read a pair written by [`write_bvarint_pair`] from `r`.
+pub fn read_bvarint_pair(mut r: impl io::Read) -> io::Result<(u64, u64)> {
+    let a = read_bvarint(&mut r)?;
+    let b = read_bvarint(&mut r)?;
+    Ok((a, b))
+}
+
+/// Encode a non-decreasing sequence as its first value followed by the
+/// differences between neighbours, and write it to `w`.
+///
+/// Return `InvalidInput` without writing anything if `values` decreases.
+pub fn write_bvarint_deltas(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(values.len());
+    let mut prev = 0;
+    for &v in values {
+        let delta = v
+            .checked_sub(prev)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "values are not sorted"))?;
+        buf.push_bvarint(delta);
+        prev = v;
+    }
+    w.write_all(&buf)
+}
+
+/// Read `count` values written by [`write_bvarint_deltas`] from `r`.
+pub fn read_bvarint_deltas(mut r: impl io::Read, count: usize) -> io::Result<Vec<u64>> {
+    let mut values = Vec::with_capacity(count.min(MAX_PREALLOC));
+    let mut prev = 0u64;
+    for _ in 0..count {
+        let delta = read_bvarint(&mut r)?;
+        prev = prev
+            .checked_add(delta)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "exceeds u64::MAX"))?;
+        values.push(prev);
+    }
+    Ok(values)
+}
+
+/// Encode `map` as its length, then each entry as the difference from the
+/// previous key followed by the value, and write it to `w`.
+///
+/// The first key is stored as is. Sparse maps with nearby keys stay small.
+pub fn write_bvarint_map(map: &BTreeMap<u64, u64>, mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = Vec::new();
+    buf.push_bvarint(map.len() as u64);
+    let mut prev = 0;
+    for (&k, &v) in map {
+        buf.push_bvarint(k - prev);
+        buf.push_bvarint(v);
+        prev = k;
+    }
+    w.write_all(&buf)
+}
+
+/// Read a map written by [`write_bvarint_map`] from `r`.
+///
+/// A key difference of 0 after the first entry, which would repeat a key,
+/// or a key above `u64::MAX` is `InvalidData`.
+pub fn read_bvarint_map(mut r: impl io::Read) -> io::Result<BTreeMap<u64, u64>> {
+    let len = read_bvarint(&mut r)?;
+    let mut map = BTreeMap::new();
+    let mut prev: Option<u64> = None;
+    for _ in 0..len {
+        let (delta, v) = read_bvarint_pair(&mut r)?;
+        let k = match prev {
+            None => delta,
+            Some(_) if delta == 0 => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "duplicate key"));
+            }
+            Some(p) => p
+                .checked_add(delta)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "exceeds u64::MAX"))?,
+        };
+        map.insert(k, v);
+        prev = Some(k);
+    }
+    Ok(map)
+}
+
+/// Encode `values` as `(value, run length)` pairs and write them to `w`.
+///
+/// Each run of equal neighbours costs two bvarints, so this only pays off
+/// for repetitive data. Without repeats, every value gains a 1-byte run
+/// length of 1.
+pub fn write_bvarint_rle(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = Vec::new();
+    let mut rest = values;
+    while let Some(&v) = rest.first() {
+        let run = rest.iter().take_while(|&&x| x == v).count();
+        buf.push_bvarint(v);
+        buf.push_bvarint(run as u64);
+        rest = &rest[run..];
+    }
+    w.write_all(&buf)
+}
+
+/// Read runs written by [`write_bvarint_rle`] from `r` until `total` values
+/// are expanded.
+///
+/// A run of length 0, or one that goes past `total`, is `InvalidData`.
+/// Memory is reserved as runs are read, not for `total` up front, but a
+/// single run may still expand to all of `total`.
+pub fn read_bvarint_rle(mut r: impl io::Read, total: usize) -> io::Result<Vec<u64>> {
+    let mut values = Vec::with_capacity(total.min(MAX_PREALLOC));
+    while values.len() < total {
+        let (v, run) = read_bvarint_pair(&mut r)?;
+        if run == 0 || run > (total - values.len()) as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "run length is 0 or exceeds the total",
+            ));
+        }
+        values.resize(values.len() + run as usize, v);
+    }
+    Ok(values)
+}
+
+/// Decode every value from `r` and write its canonical encoding to `w`.
+///
+/// This normalizes data with overlong encodings, which
+/// [`validate_bvarint`](crate::validate_bvarint) rejects. Return the number
+/// of values. A truncated value at the end is an error, after the values
+/// before it were written.
+pub fn reencode_stream(r: impl io::Read, w: impl io::Write) -> io::Result<usize> {
+    transform_stream(r, w, |v| v)
+}
+
+/// Decode every value from `r`, map it with `f`, and write the result to
+/// `w`.
+///
+/// Return the number of values. Like [`reencode_stream`], a truncated value
+/// at the end is an error, after the values before it were written.
+pub fn transform_stream(
+    mut r: impl io::Read,
+    mut w: impl io::Write,
+    mut f: impl FnMut(u64) -> u64,
+) -> io::Result<usize> {
+    let mut count = 0;
+    while let Some(v) = read_bvarint_opt(&mut r)? {
+        write_bvarint(f(v), &mut w)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+    use crate::DecodeError;
+
+    fn check_round_trip_slice(values: Vec<u64>) {
+        let mut buf = Vec::new();
+        write_bvarint_slice(&values, &mut buf).unwrap();
+        let decoded = read_bvarint_vec(&buf[..], values.len()).unwrap();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_round_trip_slice_manual() {
+        check_round_trip_slice(Vec::new());
+        check_round_trip_slice(interesting_values());
+    }
+
+    #[test]
+    fn test_read_vec_truncated() {
+        let mut buf = Vec::new();
+        write_bvarint_slice(&[1, 2], &mut buf).unwrap();
+        let err = read_bvarint_vec(&buf[..], 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = read_bvarint_vec(&buf[..], usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_exact_count() {
+        let values = interesting_values();
+        let buf = encode_all_to_vec(&values);
+        assert_eq!(
+            read_bvarint_exact_count(&buf[..], values.len()).unwrap(),
+            values
+        );
+        assert!(read_bvarint_exact_count(&[][..], 0).unwrap().is_empty());
+
+        // One value too many in the payload.
+        let err = read_bvarint_exact_count(&buf[..], values.len() - 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // One value too few.
+        let err = read_bvarint_exact_count(&buf[..], values.len() + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().starts_with("value "));
+
+        // The reason survives the index being added.
+        let mut buf = encode_all_to_vec(&[1, 2]);
+        buf.push(0xff);
+        let err = read_bvarint_exact_count(&buf[..], 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("value 2 of 3: "), "{}", err);
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::ReservedLeadByte { offset: 0 }
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_column() {
+        for values in [Vec::new(), interesting_values()] {
+            let mut buf = Vec::new();
+            write_bvarint_column(&values, &mut buf).unwrap();
+            assert_eq!(read_bvarint_column(&buf[..]).unwrap(), values);
+        }
+    }
+
+    #[test]
+    fn test_column_errors() {
+        let mut buf = Vec::new();
+        write_bvarint_column(&[1, 0x1234], &mut buf).unwrap();
+        let err = read_bvarint_column(&buf[..buf.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // A huge count fails at EOF, without reserving for it. On 32-bit,
+        // u64::MAX does not fit in usize.
+        let mut buf = Vec::new();
+        write_bvarint(u64::MAX, &mut buf).unwrap();
+        let err = read_bvarint_column(&buf[..]).unwrap_err();
+        let expected = if cfg!(target_pointer_width = "64") {
+            io::ErrorKind::UnexpectedEof
+        } else {
+            io::ErrorKind::OutOfMemory
+        };
+        assert_eq!(err.kind(), expected);
+
+        let mut buf = Vec::new();
+        write_bvarint(u32::MAX as u64, &mut buf).unwrap();
+        write_bvarint_slice(&[1, 0x1234], &mut buf).unwrap();
+        let err = read_bvarint_column(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    fn check_block_adaptive(values: &[u64], mode: u8) {
+        let mut buf = Vec::new();
+        write_bvarint_block_adaptive(values, &mut buf).unwrap();
+        assert_eq!(buf[0], mode);
+        let packed = encode_all_to_vec(values).len();
+        assert_eq!(buf.len(), 1 + packed.min(8 * values.len()));
+        assert_eq!(
+            read_bvarint_block_adaptive(&buf[..], values.len()).unwrap(),
+            values
+        );
+    }
+
+    #[test]
+    fn test_block_adaptive() {
+        let small: Vec<u64> = (0..100).collect();
+        check_block_adaptive(&small, BLOCK_BVARINT);
+        let large: Vec<u64> = (1..100u64)
+            .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15) | 1 << 63)
+            .collect();
+        check_block_adaptive(&large, BLOCK_RAW);
+        check_block_adaptive(&[], BLOCK_BVARINT);
+
+        let err = read_bvarint_block_adaptive(&[2][..], 0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_block_adaptive(&[BLOCK_RAW, 1, 2][..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        for mode in [BLOCK_BVARINT, BLOCK_RAW] {
+            let err = read_bvarint_block_adaptive(&[mode, 1][..], usize::MAX).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn test_read_array() {
+        let mut buf = Vec::new();
+        write_bvarint_slice(&[1, 0x1234, u64::MAX], &mut buf).unwrap();
+        assert_eq!(
+            read_bvarint_array::<3>(&buf[..]).unwrap(),
+            [1, 0x1234, u64::MAX]
+        );
+        assert_eq!(read_bvarint_array::<0>(&buf[..]).unwrap(), []);
+
+        let err = read_bvarint_array::<3>(&buf[..buf.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(err.to_string().starts_with("value 2 of 3: "), "{}", err);
+
+        let err = read_bvarint_array::<2>(&[1, 0xff][..]).unwrap_err();
+        assert!(err.to_string().starts_with("value 1 of 2: "), "{}", err);
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::ReservedLeadByte { offset: 0 }
+        ));
+    }
+
+    fn check_round_trip_pair(a: u64, b: u64) {
+        let mut buf = Vec::new();
+        write_bvarint_pair(a, b, &mut buf).unwrap();
+        assert_eq!(read_bvarint_pair(&buf[..]).unwrap(), (a, b));
+    }
+
+    #[test]
+    fn test_round_trip_pair_manual() {
+        let values = interesting_values();
+        for (&a, &b) in values.iter().zip(values.iter().rev()) {
+            check_round_trip_pair(a, b);
+        }
+        check_round_trip_pair(u64::MAX, u64::MAX);
+    }
+
+    /// Count `write` calls, and keep the written bytes.
+    #[derive(Default)]
+    struct CountWrites(usize, Vec<u8>);
+
+    impl io::Write for CountWrites {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += 1;
+            self.1.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_encode_many() {
+        let values: Vec<u64> = (0..10000u64)
+            .map(|i| i.wrapping_mul(0x9e3779b97f4a7c15))
+            .collect();
+        let mut w = CountWrites::default();
+        let written = encode_many(values.iter().copied(), &mut w).unwrap();
+        let expected = encode_all_to_vec(&values);
+        assert_eq!(written, expected.len() as u64);
+        assert_eq!(w.1, expected);
+        assert!(w.0 <= expected.len() / 4000 + 1, "{} writes", w.0);
+
+        let mut w = CountWrites::default();
+        assert_eq!(encode_many(None.into_iter(), &mut w).unwrap(), 0);
+        assert!(w.1.is_empty());
+    }
+
+    #[test]
+    fn test_pair_single_write() {
+        let mut w = CountWrites::default();
+        write_bvarint_pair(u64::MAX, u64::MAX, &mut w).unwrap();
+        assert_eq!(w.0, 1);
+    }
+
+    #[test]
+    fn test_round_trip_pair_quickcheck() {
+        quickcheck(check_round_trip_pair as fn(u64, u64));
+    }
+
+    fn check_round_trip_deltas(mut values: Vec<u64>) {
+        values.sort_unstable();
+        let mut buf = Vec::new();
+        write_bvarint_deltas(&values, &mut buf).unwrap();
+        let decoded = read_bvarint_deltas(&buf[..], values.len()).unwrap();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_round_trip_deltas_manual() {
+        check_round_trip_deltas(Vec::new());
+        check_round_trip_deltas(interesting_values());
+
+        let mut buf = Vec::new();
+        write_bvarint_deltas(&[1000, 1001, 1001, 1010], &mut buf).unwrap();
+        assert_eq!(buf, [0xf8, 0x03, 0xe8, 1, 0, 9]);
+    }
+
+    #[test]
+    fn test_deltas_errors() {
+        let mut buf = Vec::new();
+        let err = write_bvarint_deltas(&[2, 1], &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(buf.is_empty());
+
+        write_bvarint_slice(&[u64::MAX, 1], &mut buf).unwrap();
+        let err = read_bvarint_deltas(&buf[..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_deltas(&[1][..], usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_deltas_quickcheck() {
+        quickcheck(check_round_trip_deltas as fn(Vec<u64>));
+    }
+
+    fn check_round_trip_rle(values: Vec<u64>) {
+        let mut buf = Vec::new();
+        write_bvarint_rle(&values, &mut buf).unwrap();
+        assert_eq!(read_bvarint_rle(&buf[..], values.len()).unwrap(), values);
+    }
+
+    #[test]
+    fn test_round_trip_rle_manual() {
+        check_round_trip_rle(Vec::new());
+        check_round_trip_rle(interesting_values());
+
+        let mut values = vec![7; 1000];
+        values.extend_from_slice(&[u64::MAX; 300]);
+        values.push(7);
+        let mut buf = Vec::new();
+        write_bvarint_rle(&values, &mut buf).unwrap();
+        assert_eq!(buf.len(), 18);
+        assert_eq!(read_bvarint_rle(&buf[..], values.len()).unwrap(), values);
+
+        buf.clear();
+        write_bvarint_rle(&[1, 2], &mut buf).unwrap();
+        assert_eq!(buf, [1, 1, 2, 1]);
+    }
+
+    #[test]
+    fn test_rle_errors() {
+        let mut buf = Vec::new();
+        write_bvarint_rle(&[5; 10], &mut buf).unwrap();
+        assert_eq!(read_bvarint_rle(&buf[..], 0).unwrap(), []);
+        let err = read_bvarint_rle(&buf[..], 9).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_rle(&[5, 0][..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_rle(&buf[..], 11).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = read_bvarint_rle(&buf[..], usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_rle_quickcheck() {
+        quickcheck(check_round_trip_rle as fn(Vec<u64>));
+    }
+
+    fn check_round_trip_map(map: BTreeMap<u64, u64>) {
+        let mut buf = Vec::new();
+        write_bvarint_map(&map, &mut buf).unwrap();
+        assert_eq!(read_bvarint_map(&buf[..]).unwrap(), map);
+    }
+
+    #[test]
+    fn test_round_trip_map_manual() {
+        check_round_trip_map(BTreeMap::new());
+        let values = interesting_values();
+        check_round_trip_map(
+            values
+                .iter()
+                .zip(values.iter().rev())
+                .map(|(&k, &v)| (k, v))
+                .collect(),
+        );
+
+        let map: BTreeMap<u64, u64> = [(1000, 1), (1001, 2), (1010, 0)].iter().copied().collect();
+        let mut buf = Vec::new();
+        write_bvarint_map(&map, &mut buf).unwrap();
+        assert_eq!(buf, [3, 0xf8, 0x03, 0xe8, 1, 1, 2, 9, 0]);
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let err = read_bvarint_map(&[2, 5, 1, 0, 2][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "duplicate key");
+
+        let mut buf = vec![2];
+        write_bvarint_slice(&[u64::MAX, 1, 1, 1], &mut buf).unwrap();
+        let err = read_bvarint_map(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = read_bvarint_map(&[2, 5, 1][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_map_quickcheck() {
+        quickcheck(check_round_trip_map as fn(BTreeMap<u64, u64>));
+    }
+
+    #[test]
+    fn test_reencode_stream() {
+        let input = [
+            1, 0xf7, 0x02, 0xf8, 0x00, 0xff, 0xfe, 0, 0, 0, 0, 0, 0, 0x12, 0x34,
+        ];
+        let mut out = Vec::new();
+        assert_eq!(reencode_stream(&input[..], &mut out).unwrap(), 4);
+        assert_eq!(out, [1, 2, 0xf7, 0xff, 0xf8, 0x12, 0x34]);
+
+        let mut out = Vec::new();
+        let err = reencode_stream(&[1, 0xf7, 0x02, 0xf8, 0x00][..], &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(out, [1, 2]);
+    }
+
+    #[test]
+    fn test_transform_stream() {
+        let values = [0, 0xf6, 0xff, 0xffff, u64::MAX - 1];
+        let mut input = Vec::new();
+        write_bvarint_slice(&values, &mut input).unwrap();
+        let mut out = Vec::new();
+        assert_eq!(
+            transform_stream(&input[..], &mut out, |x| x + 1).unwrap(),
+            5
+        );
+        let incremented: Vec<u64> = values.iter().map(|x| x + 1).collect();
+        assert_eq!(out, encode_all_to_vec(&incremented));
+
+        let mut out = Vec::new();
+        let err = transform_stream(&input[..input.len() - 1], &mut out, |x| x).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(out, encode_all_to_vec(&values[..4]));
+    }
+
+    #[test]
+    fn test_round_trip_slice_quickcheck() {
+        quickcheck(check_round_trip_slice as fn(Vec<u64>));
+    }
+}
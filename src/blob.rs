@@ -0,0 +1,185 @@
+//! Length-prefixed byte blobs.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use std::io;
+use std::io::Read;
+
+/// Write `data` prefixed by its bvarint encoded length to `w`.
+pub fn write_bvarint_prefixed_bytes(data: &[u8], mut w: impl io::Write) -> io::Result<()> {
+    write_bvarint(data.len() as u64, &mut w)?;
+    w.write_all(data)
+}
+
+/// Read a blob written by [`write_bvarint_prefixed_bytes`] from `r`.
+///
+/// If `max_len` is set, longer blobs are rejected before reading them.
+/// Failing to allocate the buffer is reported as `OutOfMemory` instead of
+/// aborting.
+pub fn read_bvarint_prefixed_bytes(
+    mut r: impl io::Read,
+    max_len: Option<usize>,
+) -> io::Result<Vec<u8>> {
+    let len = read_bvarint(&mut r)?;
+    if let Some(max_len) = max_len {
+        if len > max_len as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("length {} exceeds max {}", len, max_len),
+            ));
+        }
+    }
+    // With `max_len`, reserve `len` at once. Otherwise grow the buffer as
+    // data arrives instead of trusting `len` upfront. Either way `step`
+    // fits in `usize`.
+    const CHUNK: u64 = 64 * 1024;
+    let step = if max_len.is_some() { len } else { CHUNK };
+    let mut data = Vec::new();
+    let mut r = r.take(len);
+    while (data.len() as u64) < len {
+        let want = (len - data.len() as u64).min(step);
+        data.try_reserve_exact(want as usize)
+            .map_err(|e| io::Error::new(io::ErrorKind::OutOfMemory, e))?;
+        if (&mut r).take(want).read_to_end(&mut data)? < want as usize {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+    }
+    Ok(data)
+}
+
+/// An `io::Write` adapter that buffers the bytes written to it, and on
+/// [`flush`](io::Write::flush) writes them to the inner writer as one
+/// blob, like [`write_bvarint_prefixed_bytes`].
+///
+/// Each flush with buffered bytes produces one frame. A flush with nothing
+/// buffered writes nothing, so there are no empty frames. The inner writer
+/// is flushed after each flush.
+///
+/// Buffered bytes are flushed on drop, and errors from that are ignored,
+/// so call `flush` to observe them. If writing a frame fails, part of it
+/// may already have been written.
+pub struct BvarintChunkWriter<W: io::Write> {
+    inner: W,
+    buf: Vec<u8>,
+}
+
+impl<W: io::Write> BvarintChunkWriter<W> {
+    /// Wrap `w`, with nothing buffered yet.
+    pub fn new(w: W) -> Self {
+        Self {
+            inner: w,
+            buf: Vec::new(),
+        }
+    }
+}
+
+impl<W: io::Write> io::Write for BvarintChunkWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            write_bvarint_prefixed_bytes(&self.buf, &mut self.inner)?;
+            self.buf.clear();
+        }
+        self.inner.flush()
+    }
+}
+
+impl<W: io::Write> Drop for BvarintChunkWriter<W> {
+    fn drop(&mut self) {
+        let _ = io::Write::flush(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip_blobs(blobs: Vec<Vec<u8>>) {
+        let mut buf = Vec::new();
+        for blob in &blobs {
+            write_bvarint_prefixed_bytes(blob, &mut buf).unwrap();
+        }
+        let mut r = &buf[..];
+        for blob in &blobs {
+            assert_eq!(&read_bvarint_prefixed_bytes(&mut r, None).unwrap(), blob);
+        }
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_blobs_manual() {
+        check_round_trip_blobs(vec![vec![], vec![1], vec![0xff; 0x1234]]);
+    }
+
+    #[test]
+    fn test_max_len() {
+        let mut buf = Vec::new();
+        write_bvarint_prefixed_bytes(b"abc", &mut buf).unwrap();
+        assert_eq!(
+            read_bvarint_prefixed_bytes(&buf[..], Some(3)).unwrap(),
+            b"abc"
+        );
+        let err = read_bvarint_prefixed_bytes(&buf[..], Some(2)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_truncated() {
+        // Claims u64::MAX bytes but has only one.
+        let buf = [0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 1];
+        let err = read_bvarint_prefixed_bytes(&buf[..], None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_chunk_writer() {
+        use std::io::Write;
+        let mut out = Vec::new();
+        {
+            let mut w = BvarintChunkWriter::new(&mut out);
+            w.write_all(b"hello").unwrap();
+            w.write_all(b" world").unwrap();
+            w.flush().unwrap();
+            w.flush().unwrap();
+            write!(w, "{}", 42).unwrap();
+        }
+        let mut r = &out[..];
+        assert_eq!(
+            read_bvarint_prefixed_bytes(&mut r, None).unwrap(),
+            b"hello world"
+        );
+        assert_eq!(read_bvarint_prefixed_bytes(&mut r, None).unwrap(), b"42");
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_chunked() {
+        let blob: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let mut buf = Vec::new();
+        write_bvarint_prefixed_bytes(&blob, &mut buf).unwrap();
+        assert_eq!(read_bvarint_prefixed_bytes(&buf[..], None).unwrap(), blob);
+        let err = read_bvarint_prefixed_bytes(&buf[..buf.len() - 1], None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "Miri aborts on huge allocations")]
+    fn test_allocation_failure() {
+        // Claims 2^62 bytes, allowed by `max_len`, so it is reserved upfront.
+        let mut buf = Vec::new();
+        crate::write_bvarint(1 << 62, &mut buf).unwrap();
+        buf.push(1);
+        let err = read_bvarint_prefixed_bytes(&buf[..], Some(usize::MAX)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::OutOfMemory);
+    }
+
+    #[test]
+    fn test_round_trip_blobs_quickcheck() {
+        quickcheck(check_round_trip_blobs as fn(Vec<Vec<u8>>));
+    }
+}
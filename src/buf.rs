@@ -0,0 +1,89 @@
+//! `bytes` `Buf` / `BufMut` support.
+
+use crate::encode_bvarint;
+use crate::peek_len;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use bytes::Buf;
+use bytes::BufMut;
+
+/// Encode `v` and append it to `buf`.
+pub fn put_bvarint(buf: &mut impl BufMut, v: u64) {
+    let mut a = [0; MAX_ENCODED_LEN];
+    let len = encode_bvarint(v, &mut a).expect("9 bytes fit any u64");
+    buf.put_slice(&a[..len]);
+}
+
+/// Decode an integer from the front of `buf` and advance past it.
+///
+/// If `buf` does not contain a complete value, nothing is consumed.
+pub fn get_bvarint(buf: &mut impl Buf) -> Result<u64, DecodeError> {
+    if !buf.has_remaining() {
+        return Err(DecodeError::UnexpectedEof { offset: 0 });
+    }
+    let lead = buf.chunk()[0];
+    match peek_len(lead) {
+        0 => Err(DecodeError::ReservedLeadByte { offset: 0 }),
+        1 => {
+            buf.advance(1);
+            Ok(lead as _)
+        }
+        len if buf.remaining() < len => Err(DecodeError::UnexpectedEof { offset: 0 }),
+        len => {
+            buf.advance(1);
+            let mut a = [0; 8];
+            buf.copy_to_slice(&mut a[(9 - len)..]);
+            Ok(u64::from_be_bytes(a))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::write_bvarint;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_round_trip() {
+        let mut buf = BytesMut::new();
+        let mut expected = Vec::new();
+        for x in interesting_values() {
+            put_bvarint(&mut buf, x);
+            write_bvarint(x, &mut expected).unwrap();
+        }
+        assert_eq!(&buf[..], &expected[..]);
+        let mut buf = buf.freeze();
+        for x in interesting_values() {
+            assert_eq!(get_bvarint(&mut buf).unwrap(), x);
+        }
+        assert!(matches!(
+            get_bvarint(&mut buf),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+
+    #[test]
+    fn test_truncated_not_consumed() {
+        let mut buf = &[0xf9, 1, 2][..];
+        assert!(matches!(
+            get_bvarint(&mut buf),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert_eq!(buf.len(), 3);
+
+        // Non-contiguous buffers are handled.
+        let mut buf = (&[0xf8][..]).chain(&[0x12, 0x34][..]);
+        assert_eq!(get_bvarint(&mut buf).unwrap(), 0x1234);
+    }
+
+    #[test]
+    fn test_reserved() {
+        let mut buf = &[0xff, 0, 0][..];
+        assert!(matches!(
+            get_bvarint(&mut buf),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+    }
+}
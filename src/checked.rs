@@ -0,0 +1,84 @@
+//! Values followed by a 1-byte checksum, for detecting corruption.
+//!
+//! The checksum is the XOR of the encoded bytes, so any single flipped bit
+//! is detected. It is a lightweight check, not protection against
+//! deliberate tampering.
+
+use crate::encode_to_stack;
+use crate::read_bvarint_with_bytes;
+use crate::MAX_ENCODED_LEN;
+use std::io;
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |c, b| c ^ b)
+}
+
+/// Encode `v`, followed by the XOR of the encoded bytes, and write it to
+/// `w` in one `write_all`.
+pub fn write_bvarint_checked(v: u64, mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = [0; MAX_ENCODED_LEN + 1];
+    let (encoded, len) = encode_to_stack(v);
+    buf[..len].copy_from_slice(&encoded[..len]);
+    buf[len] = checksum(&encoded[..len]);
+    w.write_all(&buf[..len + 1])
+}
+
+/// Read a value written by [`write_bvarint_checked`] from `r`.
+///
+/// A checksum mismatch is `InvalidData`.
+pub fn read_bvarint_checked(mut r: impl io::Read) -> io::Result<u64> {
+    let (v, bytes, len) = read_bvarint_with_bytes(&mut r)?;
+    let mut c = [0];
+    r.read_exact(&mut c)?;
+    if c[0] != checksum(&bytes[..len]) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "checksum mismatch",
+        ));
+    }
+    Ok(v)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip(x: u64) {
+        let mut buf = Vec::new();
+        write_bvarint_checked(x, &mut buf).unwrap();
+        assert_eq!(buf.len(), crate::encoded_len(x) + 1);
+        assert_eq!(read_bvarint_checked(&buf[..]).unwrap(), x);
+    }
+
+    #[test]
+    fn test_round_trip_manual() {
+        for x in interesting_values() {
+            check_round_trip(x);
+        }
+    }
+
+    #[test]
+    fn test_bit_flip() {
+        let mut buf = Vec::new();
+        write_bvarint_checked(0x12345678, &mut buf).unwrap();
+        // Flips in the lead byte may change the length and fail otherwise,
+        // so only check the payload and the checksum.
+        for i in 1..buf.len() {
+            for bit in 0..8 {
+                let mut corrupt = buf.clone();
+                corrupt[i] ^= 1 << bit;
+                let err = read_bvarint_checked(&corrupt[..]).unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            }
+        }
+        let err = read_bvarint_checked(&buf[..buf.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_quickcheck() {
+        quickcheck(check_round_trip as fn(u64));
+    }
+}
@@ -0,0 +1,69 @@
+//! Human-readable dumps of encodings.
+
+use crate::encode_to_stack;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "alloc")]
+use core::fmt::Write;
+
+/// Format the encoding of a value as hex without allocating.
+///
+/// `format!("{:x}", HexEncoded(0x1234))` is `"f81234"`. The `#` flag adds
+/// a `0x` prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HexEncoded(pub u64);
+
+impl fmt::LowerHex for HexEncoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (buf, len) = encode_to_stack(self.0);
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        for b in &buf[..len] {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Describe how `v` is encoded, like `"0x107f0 -> [f9, 01, 07, f0] (4 bytes)"`.
+#[cfg(feature = "alloc")]
+pub fn debug_encoding(v: u64) -> String {
+    let (buf, len) = encode_to_stack(v);
+    let mut s = String::new();
+    write!(s, "{:#x} -> [", v).unwrap();
+    for (i, b) in buf[..len].iter().enumerate() {
+        if i > 0 {
+            s.push_str(", ");
+        }
+        write!(s, "{:02x}", b).unwrap();
+    }
+    let unit = if len == 1 { "byte" } else { "bytes" };
+    write!(s, "] ({} {})", len, unit).unwrap();
+    s
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_encoding() {
+        assert_eq!(debug_encoding(0), "0x0 -> [00] (1 byte)");
+        assert_eq!(debug_encoding(0xf7), "0xf7 -> [f7, f7] (2 bytes)");
+        assert_eq!(
+            debug_encoding(0x107f0),
+            "0x107f0 -> [f9, 01, 07, f0] (4 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_hex_encoded() {
+        assert_eq!(format!("{:x}", HexEncoded(0)), "00");
+        assert_eq!(format!("{:x}", HexEncoded(0xf7)), "f7f7");
+        assert_eq!(format!("{:x}", HexEncoded(0x1234)), "f81234");
+        assert_eq!(format!("{:#x}", HexEncoded(0x107f0)), "0xf90107f0");
+        assert_eq!(format!("{:x}", HexEncoded(u64::MAX)), "feffffffffffffffff");
+    }
+}
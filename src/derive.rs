@@ -0,0 +1,41 @@
+//! Traits for types encoded as a sequence of bvarints.
+//!
+//! With the `derive` feature, `#[derive(BvarintEncode, BvarintDecode)]`
+//! implements them for structs by encoding each field in declaration
+//! order. Fields marked `#[bvarint(signed)]` are encoded like
+//! [`write_bvarint_i64`](crate::write_bvarint_i64).
+
+use crate::BvarintInt;
+use std::io;
+
+/// Types that can be written as bvarints.
+pub trait BvarintEncode {
+    /// Encode `self` and write it to `w`.
+    fn write_bvarint<W: io::Write>(&self, w: W) -> io::Result<()>;
+}
+
+/// Types that can be read from bvarints.
+pub trait BvarintDecode: Sized {
+    /// Read from `r` and return the decoded value.
+    fn read_bvarint<R: io::Read>(r: R) -> io::Result<Self>;
+}
+
+macro_rules! impl_encode_decode {
+    ($($ty:ty),*) => {
+        $(
+            impl BvarintEncode for $ty {
+                fn write_bvarint<W: io::Write>(&self, w: W) -> io::Result<()> {
+                    BvarintInt::write_bvarint(*self, w)
+                }
+            }
+
+            impl BvarintDecode for $ty {
+                fn read_bvarint<R: io::Read>(r: R) -> io::Result<Self> {
+                    <$ty as BvarintInt>::read_bvarint(r)
+                }
+            }
+        )*
+    };
+}
+
+impl_encode_decode!(u8, u16, u32, u64, usize);
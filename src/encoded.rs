@@ -0,0 +1,216 @@
+//! Encoded values stored inline.
+
+use crate::decode_bvarint;
+use crate::encode_to_stack;
+use crate::validate_bvarint_exact;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::hash::Hash;
+use core::hash::Hasher;
+
+/// The encoding of a `u64`, stored inline.
+///
+/// Because the encoding is order-preserving, the `Ord` of the bytes matches
+/// the `Ord` of the values, so a `BTreeMap<EncodedU64, _>` sorts like a
+/// `BTreeMap<u64, _>`. Equality and hashing also only look at the encoded
+/// bytes, not the unused part of the buffer, so it works as a `HashMap` key.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodedU64([u8; MAX_ENCODED_LEN], u8);
+
+impl EncodedU64 {
+    /// Encode `v`.
+    pub const fn new(v: u64) -> Self {
+        let (buf, len) = encode_to_stack(v);
+        Self(buf, len as u8)
+    }
+
+    /// Decode the value.
+    pub fn value(&self) -> u64 {
+        decode_bvarint(self.as_bytes())
+            .expect("EncodedU64 holds a valid encoding")
+            .0
+    }
+
+    /// The encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0[..self.1 as usize]
+    }
+}
+
+impl AsRef<[u8]> for EncodedU64 {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl PartialEq for EncodedU64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for EncodedU64 {}
+
+impl Hash for EncodedU64 {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl Ord for EncodedU64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl PartialOrd for EncodedU64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<u64> for EncodedU64 {
+    fn from(v: u64) -> Self {
+        Self::new(v)
+    }
+}
+
+impl From<EncodedU64> for u64 {
+    fn from(e: EncodedU64) -> Self {
+        e.value()
+    }
+}
+
+/// Validate that the bytes are exactly one canonical encoding, for
+/// example a key received from a client.
+impl<'a> TryFrom<&'a [u8]> for EncodedU64 {
+    type Error = DecodeError;
+
+    fn try_from(buf: &'a [u8]) -> Result<Self, DecodeError> {
+        validate_bvarint_exact(buf)?;
+        let mut bytes = [0; MAX_ENCODED_LEN];
+        bytes[..buf.len()].copy_from_slice(buf);
+        Ok(Self(bytes, buf.len() as u8))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+    use crate::write_bvarint;
+
+    fn check_order(a: u64, b: u64) {
+        assert_eq!(
+            EncodedU64::new(a).cmp(&EncodedU64::new(b)),
+            a.cmp(&b),
+            "check_order(0x{:x}, 0x{:x})",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for x in interesting_values() {
+            let encoded = EncodedU64::new(x);
+            let mut expected = Vec::new();
+            write_bvarint(x, &mut expected).unwrap();
+            assert_eq!(encoded.as_bytes(), &expected[..]);
+            assert_eq!(encoded.value(), x);
+        }
+    }
+
+    #[test]
+    fn test_into() {
+        for x in interesting_values() {
+            let encoded: EncodedU64 = x.into();
+            let decoded: u64 = encoded.into();
+            assert_eq!(decoded, x);
+        }
+    }
+
+    #[test]
+    fn test_try_from() {
+        for x in interesting_values() {
+            let encoded = EncodedU64::new(x);
+            assert_eq!(EncodedU64::try_from(encoded.as_bytes()).unwrap(), encoded);
+        }
+        assert!(matches!(
+            EncodedU64::try_from(&[0xf7, 0x01][..]),
+            Err(DecodeError::NonCanonical)
+        ));
+        assert!(matches!(
+            EncodedU64::try_from(&[0xf8, 0x12, 0x34, 0x00][..]),
+            Err(DecodeError::TrailingBytes {
+                consumed: 3,
+                remaining: 1
+            })
+        ));
+        assert!(matches!(
+            EncodedU64::try_from(&[0xf8, 0x12][..]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(
+            EncodedU64::try_from(&[0xff][..]),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+        assert!(EncodedU64::try_from(&[][..]).is_err());
+    }
+
+    #[test]
+    fn test_order_manual() {
+        let values = interesting_values();
+        for &a in &values {
+            for &b in &values {
+                check_order(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_btree_map() {
+        let values = interesting_values();
+        let map: std::collections::BTreeMap<EncodedU64, u64> =
+            values.iter().rev().map(|&v| (v.into(), v)).collect();
+        let mut sorted = values;
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(map.into_values().collect::<Vec<_>>(), sorted);
+    }
+
+    #[test]
+    fn test_hash_map() {
+        let values = interesting_values();
+        let map: std::collections::HashMap<EncodedU64, u64> =
+            values.iter().map(|&v| (v.into(), v)).collect();
+        for &x in &values {
+            assert_eq!(map[&EncodedU64::new(x)], x);
+        }
+    }
+
+    #[test]
+    fn test_padding_ignored() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |e: &EncodedU64| {
+            let mut h = DefaultHasher::new();
+            e.hash(&mut h);
+            h.finish()
+        };
+        for x in interesting_values() {
+            let clean = EncodedU64::new(x);
+            let mut dirty = clean;
+            dirty.0[clean.1 as usize..].fill(0xaa);
+            assert_eq!(dirty, clean);
+            assert_eq!(hash(&dirty), hash(&clean));
+        }
+    }
+
+    #[test]
+    fn test_order_quickcheck() {
+        quickcheck(check_order as fn(u64, u64));
+    }
+}
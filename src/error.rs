@@ -0,0 +1,240 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Error returned by slice-based encoders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The output buffer is too small. `needed` is the full encoded length.
+    BufferTooSmall { needed: usize },
+
+    /// A fixed-width slot is `width` bytes, which is not in `1..=9`.
+    InvalidWidth { width: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::BufferTooSmall { needed } => {
+                write!(f, "buffer too small ({} bytes needed)", needed)
+            }
+            EncodeError::InvalidWidth { width } => {
+                write!(f, "width {} is not in 1..=9", width)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Error returned by decoders.
+///
+/// Errors from the `io::Read` based decoders can be converted to this type
+/// using `From<io::Error>` to match on the failure reason. Without `std`,
+/// there is no `Io` variant and the type only depends on `core`.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The input ended in the middle of a value. `offset` is where the
+    /// value starts, relative to the start of the input.
+    UnexpectedEof { offset: usize },
+
+    /// The lead byte at `offset` is `0xff`, which is reserved for integers
+    /// larger than `u64::MAX`.
+    ReservedLeadByte { offset: usize },
+
+    /// The value `got` does not fit in the requested integer type, whose
+    /// maximum is `max`.
+    TypeOverflow { max: u64, got: u64 },
+
+    /// The value has a shorter encoding.
+    NonCanonical,
+
+    /// The encoding needs more bytes than the caller allowed to read.
+    LimitExceeded { needed: usize, limit: usize },
+
+    /// The input has `remaining` bytes after a value of `consumed` bytes.
+    TrailingBytes { consumed: usize, remaining: usize },
+
+    /// The underlying reader failed.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof { offset } => {
+                write!(f, "unexpected end of input (value at offset {})", offset)
+            }
+            DecodeError::ReservedLeadByte { offset } => {
+                write!(f, "exceeds u64::MAX (value at offset {})", offset)
+            }
+            DecodeError::TypeOverflow { max, got } => {
+                write!(f, "{} exceeds the maximum {}", got, max)
+            }
+            DecodeError::NonCanonical => write!(f, "non-canonical encoding"),
+            DecodeError::LimitExceeded { needed, limit } => {
+                write!(f, "needs {} bytes, exceeds limit {}", needed, limit)
+            }
+            DecodeError::TrailingBytes {
+                consumed,
+                remaining,
+            } => write!(f, "{} trailing bytes after offset {}", remaining, consumed),
+            #[cfg(feature = "std")]
+            DecodeError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl DecodeError {
+    /// Shift offsets by `base`, for errors from a sub-slice of the input.
+    pub(crate) fn with_base_offset(self, base: usize) -> Self {
+        match self {
+            DecodeError::UnexpectedEof { offset } => DecodeError::UnexpectedEof {
+                offset: base + offset,
+            },
+            DecodeError::ReservedLeadByte { offset } => DecodeError::ReservedLeadByte {
+                offset: base + offset,
+            },
+            e => e,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        let is_indexed = e.get_ref().is_some_and(|inner| inner.is::<IndexedError>());
+        if is_indexed {
+            let inner = e.into_inner().expect("checked above");
+            return DecodeError::from(
+                inner
+                    .downcast::<IndexedError>()
+                    .expect("checked above")
+                    .source,
+            );
+        }
+        let is_decode_error = e.get_ref().is_some_and(|inner| inner.is::<DecodeError>());
+        if is_decode_error {
+            let inner = e.into_inner().expect("checked above");
+            return *inner.downcast::<DecodeError>().expect("checked above");
+        }
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => DecodeError::UnexpectedEof { offset: 0 },
+            _ => DecodeError::Io(e),
+        }
+    }
+}
+
+/// An error reading one of several values, saying which one.
+///
+/// The original error is kept as the source, so `From<io::Error>` for
+/// [`DecodeError`] still recovers the reason.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(crate) struct IndexedError {
+    index: usize,
+    count: usize,
+    source: io::Error,
+}
+
+#[cfg(feature = "std")]
+impl IndexedError {
+    /// Wrap `e`, from reading value `index` of `count`, keeping its kind.
+    pub(crate) fn wrap(e: io::Error, index: usize, count: usize) -> io::Error {
+        let kind = e.kind();
+        let e = IndexedError {
+            index,
+            count,
+            source: e,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for IndexedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value {} of {}: {}", self.index, self.count, self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DecodeError> for io::Error {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::UnexpectedEof { .. } => io::Error::new(io::ErrorKind::UnexpectedEof, e),
+            DecodeError::Io(e) => e,
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// These also run without `std`, via `cargo test --no-default-features`.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::fmt::Write;
+
+    /// A fixed-capacity `fmt::Write`, to format without allocating.
+    struct StackString {
+        buf: [u8; 64],
+        len: usize,
+    }
+
+    impl Write for StackString {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.buf
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    fn check_display(e: impl fmt::Display, expected: &str) {
+        let mut out = StackString {
+            buf: [0; 64],
+            len: 0,
+        };
+        write!(out, "{}", e).unwrap();
+        assert_eq!(&out.buf[..out.len], expected.as_bytes());
+    }
+
+    #[test]
+    fn test_display() {
+        check_display(
+            EncodeError::BufferTooSmall { needed: 3 },
+            "buffer too small (3 bytes needed)",
+        );
+        check_display(
+            DecodeError::ReservedLeadByte { offset: 2 },
+            "exceeds u64::MAX (value at offset 2)",
+        );
+        check_display(
+            DecodeError::TypeOverflow { max: 255, got: 256 },
+            "256 exceeds the maximum 255",
+        );
+        check_display(DecodeError::NonCanonical, "non-canonical encoding");
+    }
+}
@@ -0,0 +1,139 @@
+//! C bindings.
+//!
+//! The functions return the number of bytes written or consumed, or one of
+//! the negative `BVARINT_ERR_*` codes. Null pointers and short buffers are
+//! reported as errors. To link from C, build a static library:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! and declare:
+//!
+//! ```c
+//! intptr_t bvarint_encode(uint64_t v, uint8_t *out, size_t out_len);
+//! intptr_t bvarint_decode(const uint8_t *buf, size_t len, uint64_t *out);
+//! ```
+
+use crate::decode_bvarint;
+use crate::encode_bvarint;
+use crate::DecodeError;
+use core::slice;
+
+/// A pointer argument is null.
+pub const BVARINT_ERR_NULL: isize = -1;
+
+/// The output buffer is too small.
+pub const BVARINT_ERR_BUFFER_TOO_SMALL: isize = -2;
+
+/// The input ends in the middle of a value.
+pub const BVARINT_ERR_UNEXPECTED_EOF: isize = -3;
+
+/// The lead byte is `0xff`, which is reserved for integers larger than
+/// `u64::MAX`.
+pub const BVARINT_ERR_OVERFLOW: isize = -4;
+
+/// Encode `v` into `out` and return the number of bytes written.
+///
+/// If `out_len` is too small, nothing is written.
+///
+/// # Safety
+///
+/// If `out` is not null, it must be valid for writes of `out_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bvarint_encode(v: u64, out: *mut u8, out_len: usize) -> isize {
+    if out.is_null() {
+        return BVARINT_ERR_NULL;
+    }
+    let out = slice::from_raw_parts_mut(out, out_len);
+    match encode_bvarint(v, out) {
+        Ok(len) => len as isize,
+        Err(_) => BVARINT_ERR_BUFFER_TOO_SMALL,
+    }
+}
+
+/// Decode an integer from the front of `buf` into `*out` and return the
+/// number of bytes consumed.
+///
+/// On error, `*out` is unchanged.
+///
+/// # Safety
+///
+/// If `buf` is not null, it must be valid for reads of `len` bytes. If
+/// `out` is not null, it must be valid for a write of a `u64`.
+#[no_mangle]
+pub unsafe extern "C" fn bvarint_decode(buf: *const u8, len: usize, out: *mut u64) -> isize {
+    if buf.is_null() || out.is_null() {
+        return BVARINT_ERR_NULL;
+    }
+    let buf = slice::from_raw_parts(buf, len);
+    match decode_bvarint(buf) {
+        Ok((v, len)) => {
+            *out = v;
+            len as isize
+        }
+        Err(DecodeError::UnexpectedEof { .. }) => BVARINT_ERR_UNEXPECTED_EOF,
+        Err(_) => BVARINT_ERR_OVERFLOW,
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::MAX_ENCODED_LEN;
+    use core::ptr;
+
+    #[test]
+    fn test_round_trip() {
+        for x in interesting_values() {
+            let mut buf = [0u8; MAX_ENCODED_LEN];
+            let len = unsafe { bvarint_encode(x, buf.as_mut_ptr(), buf.len()) };
+            assert_eq!(len as usize, crate::encoded_len(x));
+            let mut out = 0u64;
+            let consumed = unsafe { bvarint_decode(buf.as_ptr(), buf.len(), &mut out) };
+            assert_eq!((out, consumed), (x, len));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buf = [0xaau8; 2];
+        unsafe {
+            assert_eq!(bvarint_encode(1, ptr::null_mut(), 9), BVARINT_ERR_NULL);
+            assert_eq!(
+                bvarint_encode(0x1234, buf.as_mut_ptr(), buf.len()),
+                BVARINT_ERR_BUFFER_TOO_SMALL
+            );
+            assert_eq!(
+                bvarint_encode(0, buf.as_mut_ptr(), 0),
+                BVARINT_ERR_BUFFER_TOO_SMALL
+            );
+        }
+        assert_eq!(buf, [0xaa; 2]);
+
+        let mut out = 7u64;
+        unsafe {
+            assert_eq!(bvarint_decode(ptr::null(), 0, &mut out), BVARINT_ERR_NULL);
+            assert_eq!(
+                bvarint_decode(buf.as_ptr(), buf.len(), ptr::null_mut()),
+                BVARINT_ERR_NULL
+            );
+            let truncated = [0xf8, 0x12];
+            assert_eq!(
+                bvarint_decode(truncated.as_ptr(), truncated.len(), &mut out),
+                BVARINT_ERR_UNEXPECTED_EOF
+            );
+            assert_eq!(
+                bvarint_decode(truncated.as_ptr(), 0, &mut out),
+                BVARINT_ERR_UNEXPECTED_EOF
+            );
+            let reserved = [0xff, 0x12];
+            assert_eq!(
+                bvarint_decode(reserved.as_ptr(), reserved.len(), &mut out),
+                BVARINT_ERR_OVERFLOW
+            );
+        }
+        assert_eq!(out, 7);
+    }
+}
@@ -0,0 +1,259 @@
+//! Fixed-width encodings for random-access arrays.
+//!
+//! A value is padded to `width` bytes by using the lead byte for that width
+//! and zero-filling the big-endian bytes. The result is an overlong but
+//! valid encoding, so [`read_bvarint`](crate::read_bvarint) decodes it.
+//! Encodings of the same width keep the `memcmp` order.
+
+#[cfg(feature = "std")]
+use crate::decode_bvarint;
+use crate::encoded_len;
+#[cfg(feature = "std")]
+use crate::peek_len;
+use crate::EncodeError;
+use crate::MAX_ENCODED_LEN;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+fn check_width(width: usize) -> io::Result<()> {
+    if width == 0 || width > MAX_ENCODED_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("width {} is not in 1..={}", width, MAX_ENCODED_LEN),
+        ));
+    }
+    Ok(())
+}
+
+/// Encode `v` using exactly `width` bytes and write it to `w`.
+///
+/// `width` must be in `1..=9`, and at least [`encoded_len`]`(v)`.
+#[cfg(feature = "std")]
+pub fn write_bvarint_fixed(v: u64, width: usize, mut w: impl io::Write) -> io::Result<()> {
+    check_width(width)?;
+    let len = encoded_len(v);
+    if len > width {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("value {} needs {} bytes, exceeds width {}", v, len, width),
+        ));
+    }
+    let mut buf = [0; MAX_ENCODED_LEN];
+    patch_bvarint_fixed(&mut buf[..width], v).expect("checked above");
+    w.write_all(&buf[..width])
+}
+
+/// Encode `v` into all of `slot`, padded like [`write_bvarint_fixed`], to
+/// update a fixed-width value in place.
+///
+/// The slot length is the width. If `v` does not fit, `slot` is
+/// unchanged.
+pub fn patch_bvarint_fixed(slot: &mut [u8], v: u64) -> Result<(), EncodeError> {
+    let width = slot.len();
+    if width == 0 || width > MAX_ENCODED_LEN {
+        return Err(EncodeError::InvalidWidth { width });
+    }
+    let len = encoded_len(v);
+    if len > width {
+        return Err(EncodeError::BufferTooSmall { needed: len });
+    }
+    if width == 1 {
+        slot[0] = v as u8;
+    } else {
+        slot[0] = (0xf7 - 2 + width) as u8;
+        slot[1..].copy_from_slice(&v.to_be_bytes()[(9 - width)..]);
+    }
+    Ok(())
+}
+
+/// A slot reserved by [`reserve_bvarint_slot`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotHandle {
+    offset: usize,
+    width: usize,
+}
+
+/// Append a fixed-width slot wide enough for any value up to `max`, to be
+/// filled later by [`patch_bvarint_slot`], for example with a count only
+/// known after writing the items.
+///
+/// The slot holds 0 until patched.
+#[cfg(feature = "alloc")]
+pub fn reserve_bvarint_slot(buf: &mut Vec<u8>, max: u64) -> SlotHandle {
+    let offset = buf.len();
+    let width = encoded_len(max);
+    buf.resize(offset + width, 0);
+    patch_bvarint_fixed(&mut buf[offset..], 0).expect("0 fits any width");
+    SlotHandle { offset, width }
+}
+
+/// Encode `v` into the slot `handle` of `buf`.
+///
+/// If `v` exceeds what the slot can hold, `buf` is unchanged. Panics if
+/// `buf` is shorter than when the slot was reserved.
+#[cfg(feature = "alloc")]
+pub fn patch_bvarint_slot(buf: &mut [u8], handle: SlotHandle, v: u64) -> Result<(), EncodeError> {
+    patch_bvarint_fixed(&mut buf[handle.offset..][..handle.width], v)
+}
+
+/// Read a value written by [`write_bvarint_fixed`] with the same `width`
+/// from `r`.
+///
+/// Encodings of a different width are reported as `InvalidData`.
+#[cfg(feature = "std")]
+pub fn read_bvarint_fixed(mut r: impl io::Read, width: usize) -> io::Result<u64> {
+    check_width(width)?;
+    let mut buf = [0; MAX_ENCODED_LEN];
+    r.read_exact(&mut buf[..width])?;
+    let len = peek_len(buf[0]);
+    if len != 0 && len != width {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("encoding is {} bytes, expected {}", len, width),
+        ));
+    }
+    Ok(decode_bvarint(&buf[..width])?.0)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::read_bvarint;
+    use crate::tests::interesting_values;
+
+    fn encode(v: u64, width: usize) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_bvarint_fixed(v, width, &mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for x in interesting_values() {
+            for width in encoded_len(x)..=MAX_ENCODED_LEN {
+                let buf = encode(x, width);
+                assert_eq!(buf.len(), width);
+                assert_eq!(read_bvarint_fixed(&buf[..], width).unwrap(), x);
+                assert_eq!(read_bvarint(&buf[..]).unwrap(), x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_natural_width_is_canonical() {
+        for x in interesting_values() {
+            let mut expected = Vec::new();
+            crate::write_bvarint(x, &mut expected).unwrap();
+            assert_eq!(encode(x, encoded_len(x)), expected);
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let mut values = interesting_values();
+        // The small widths, where the boundaries are close together.
+        for max in [
+            crate::ONE_BYTE_MAX,
+            crate::TWO_BYTE_MAX,
+            crate::THREE_BYTE_MAX,
+        ] {
+            values.extend_from_slice(&[max - 1, max, max + 1]);
+        }
+        for width in 1..=MAX_ENCODED_LEN {
+            for &x in values.iter().filter(|&&x| encoded_len(x) <= width) {
+                for &y in values.iter().filter(|&&y| encoded_len(y) <= width) {
+                    assert_eq!(x.cmp(&y), encode(x, width).cmp(&encode(y, width)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut buf = Vec::new();
+        for &width in &[0, 10] {
+            let err = write_bvarint_fixed(1, width, &mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+        let err = write_bvarint_fixed(0x10000, 3, &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(buf.is_empty());
+
+        let err = read_bvarint_fixed(&[0xf8, 0, 1][..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_fixed(&[0xf8, 0][..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_fixed(&[0xff, 0][..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_fixed(&[0xf7][..], 2).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut buf = Vec::new();
+        for &x in &[1, 0x1234, 0x56] {
+            write_bvarint_fixed(x, 4, &mut buf).unwrap();
+        }
+        patch_bvarint_fixed(&mut buf[4..8], 0x123456).unwrap();
+        let mut r = &buf[..];
+        for &x in &[1, 0x123456, 0x56] {
+            assert_eq!(read_bvarint_fixed(&mut r, 4).unwrap(), x);
+        }
+
+        for x in interesting_values() {
+            for width in encoded_len(x)..=MAX_ENCODED_LEN {
+                let mut slot = [0xaa; MAX_ENCODED_LEN];
+                patch_bvarint_fixed(&mut slot[..width], x).unwrap();
+                assert_eq!(&slot[..width], &encode(x, width)[..]);
+                assert!(slot[width..].iter().all(|&b| b == 0xaa));
+            }
+        }
+    }
+
+    #[test]
+    fn test_patch_errors() {
+        let mut slot = [0xaa; 3];
+        assert_eq!(
+            patch_bvarint_fixed(&mut slot, 0x10000),
+            Err(EncodeError::BufferTooSmall { needed: 4 })
+        );
+        assert_eq!(slot, [0xaa; 3]);
+        assert_eq!(
+            patch_bvarint_fixed(&mut [], 0),
+            Err(EncodeError::InvalidWidth { width: 0 })
+        );
+        assert_eq!(
+            patch_bvarint_fixed(&mut [0; 10], 0),
+            Err(EncodeError::InvalidWidth { width: 10 })
+        );
+    }
+
+    #[test]
+    fn test_slot() {
+        let mut buf = vec![9];
+        let slot = reserve_bvarint_slot(&mut buf, 1000);
+        assert_eq!(read_bvarint(&buf[1..]).unwrap(), 0);
+        let items = [5, 0x1234, 7];
+        for &v in &items {
+            crate::write_bvarint(v, &mut buf).unwrap();
+        }
+        patch_bvarint_slot(&mut buf, slot, items.len() as u64).unwrap();
+        let mut r = &buf[1..];
+        assert_eq!(read_bvarint(&mut r).unwrap(), 3);
+        assert_eq!(crate::read_bvarint_vec(&mut r, 3).unwrap(), items);
+
+        let before = buf.clone();
+        assert_eq!(
+            patch_bvarint_slot(&mut buf, slot, 0x10000),
+            Err(EncodeError::BufferTooSmall { needed: 4 })
+        );
+        assert_eq!(buf, before);
+        patch_bvarint_slot(&mut buf, slot, 0xffff).unwrap();
+        assert_eq!(buf[1..4], [0xf8, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,69 @@
+//! Sets of up to 64 flags stored as a bitmask.
+//!
+//! Bit `n` is flag `n`. When mostly low flags are set, the mask is small
+//! and encodes in few bytes.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use std::io;
+
+/// A set of flags, where flag `n` is bit `n` of the mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FlagSet(pub u64);
+
+impl FlagSet {
+    /// Return whether flag `bit` is set. Flags from 64 up are never set.
+    pub fn contains(&self, bit: u32) -> bool {
+        bit < 64 && self.0 & (1 << bit) != 0
+    }
+
+    /// Iterate over the set flags in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> {
+        let mut rest = self.0;
+        core::iter::from_fn(move || {
+            if rest == 0 {
+                return None;
+            }
+            let bit = rest.trailing_zeros();
+            rest &= rest - 1;
+            Some(bit)
+        })
+    }
+}
+
+/// Encode the bitmask `flags` and write it to `w`.
+pub fn write_bvarint_flags(flags: u64, w: impl io::Write) -> io::Result<()> {
+    write_bvarint(flags, w)
+}
+
+/// Read a bitmask written by [`write_bvarint_flags`] from `r`.
+pub fn read_bvarint_flags(r: impl io::Read) -> io::Result<FlagSet> {
+    Ok(FlagSet(read_bvarint(r)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let bits = [0, 3, 17, 40, 63];
+        let mask = bits.iter().fold(0u64, |m, b| m | (1 << b));
+        let mut buf = Vec::new();
+        write_bvarint_flags(mask, &mut buf).unwrap();
+        let flags = read_bvarint_flags(&buf[..]).unwrap();
+        assert_eq!(flags, FlagSet(mask));
+        assert_eq!(flags.iter().collect::<Vec<_>>(), bits);
+        for bit in 0..70 {
+            assert_eq!(flags.contains(bit), bits.contains(&bit), "bit {}", bit);
+        }
+    }
+
+    #[test]
+    fn test_low_flags_are_small() {
+        let mut buf = Vec::new();
+        write_bvarint_flags(0b1011, &mut buf).unwrap();
+        assert_eq!(buf, [0b1011]);
+        assert_eq!(FlagSet(0).iter().count(), 0);
+    }
+}
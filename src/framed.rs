@@ -0,0 +1,176 @@
+//! `tokio_util::codec` support for length-prefixed frames.
+
+use crate::decode_bvarint;
+use crate::encoded_len;
+use crate::put_bvarint;
+use crate::DecodeError;
+use bytes::Buf;
+use bytes::BufMut;
+use bytes::Bytes;
+use bytes::BytesMut;
+use std::convert::TryFrom;
+use std::io;
+use tokio_util::codec::Decoder;
+use tokio_util::codec::Encoder;
+
+/// The default maximum frame length, 8 MiB like tokio's codec.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// Frames each payload with a bvarint length prefix.
+///
+/// Frames longer than the maximum frame length are an error when encoding
+/// and decoding. Otherwise a peer could claim a huge length and make the
+/// decoder buffer without limit.
+#[derive(Debug, Clone, Copy)]
+pub struct BvarintLengthDelimitedCodec {
+    max_frame_length: usize,
+}
+
+impl BvarintLengthDelimitedCodec {
+    /// Create a codec with the default maximum frame length of 8 MiB.
+    pub fn new() -> Self {
+        Self::with_max_frame_length(DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Create a codec accepting payloads of at most `max` bytes.
+    pub fn with_max_frame_length(max: usize) -> Self {
+        Self {
+            max_frame_length: max,
+        }
+    }
+
+    /// The longest payload accepted, in bytes.
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+
+    fn too_long(&self, kind: io::ErrorKind, len: u64) -> io::Error {
+        io::Error::new(
+            kind,
+            format!(
+                "frame of {} bytes exceeds the maximum {}",
+                len, self.max_frame_length
+            ),
+        )
+    }
+}
+
+impl Default for BvarintLengthDelimitedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder<Bytes> for BvarintLengthDelimitedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> io::Result<()> {
+        if item.len() > self.max_frame_length {
+            return Err(self.too_long(io::ErrorKind::InvalidInput, item.len() as u64));
+        }
+        let len = item.len() as u64;
+        dst.reserve(encoded_len(len) + item.len());
+        put_bvarint(dst, len);
+        dst.put(item);
+        Ok(())
+    }
+}
+
+impl Decoder for BvarintLengthDelimitedCodec {
+    type Item = BytesMut;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<BytesMut>> {
+        // Nothing is consumed until the whole frame is buffered, so check
+        // the length first to bound how much that is.
+        let (len, prefix_len) = match decode_bvarint(src) {
+            Ok(v) => v,
+            Err(DecodeError::UnexpectedEof { .. }) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let len = match usize::try_from(len) {
+            Ok(len) if len <= self.max_frame_length => len,
+            _ => return Err(self.too_long(io::ErrorKind::InvalidData, len)),
+        };
+        if src.len() - prefix_len < len {
+            return Ok(None);
+        }
+        src.advance(prefix_len);
+        Ok(Some(src.split_to(len)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_frames() {
+        let payloads: Vec<Bytes> = vec![
+            Bytes::new(),
+            Bytes::from_static(b"x"),
+            Bytes::from(vec![7u8; 300]),
+        ];
+        let mut codec = BvarintLengthDelimitedCodec::new();
+        let mut encoded = BytesMut::new();
+        for p in &payloads {
+            codec.encode(p.clone(), &mut encoded).unwrap();
+        }
+        // 300 bytes need a 3-byte length prefix.
+        assert_eq!(encoded.len(), 1 + 2 + 303);
+
+        // Feed one byte at a time.
+        let mut src = BytesMut::new();
+        let mut decoded = Vec::new();
+        for &b in encoded.iter() {
+            src.put_u8(b);
+            while let Some(frame) = codec.decode(&mut src).unwrap() {
+                decoded.push(frame.freeze());
+            }
+        }
+        assert_eq!(decoded, payloads);
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn test_prefix_kept_until_complete() {
+        let mut codec = BvarintLengthDelimitedCodec::new();
+        let mut src = BytesMut::from(&[3, b'a', b'b'][..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        assert_eq!(src.len(), 3);
+        src.put_u8(b'c');
+        assert_eq!(&codec.decode(&mut src).unwrap().unwrap()[..], b"abc");
+    }
+
+    #[test]
+    fn test_max_frame_length() {
+        let mut codec = BvarintLengthDelimitedCodec::with_max_frame_length(3);
+        assert_eq!(codec.max_frame_length(), 3);
+        let mut dst = BytesMut::new();
+        codec.encode(Bytes::from_static(b"abc"), &mut dst).unwrap();
+        let err = codec
+            .encode(Bytes::from_static(b"abcd"), &mut dst)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(&codec.decode(&mut dst).unwrap().unwrap()[..], b"abc");
+
+        // Rejected from the prefix alone, before the payload arrives.
+        let mut src = BytesMut::from(&[4][..]);
+        let err = codec.decode(&mut src).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut src = BytesMut::from(&[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff][..]);
+        assert!(codec.decode(&mut src).is_err());
+
+        assert_eq!(
+            BvarintLengthDelimitedCodec::default().max_frame_length(),
+            8 * 1024 * 1024
+        );
+    }
+
+    #[test]
+    fn test_reserved_prefix() {
+        let mut codec = BvarintLengthDelimitedCodec::new();
+        let mut src = BytesMut::from(&[0xff, 0][..]);
+        assert!(codec.decode(&mut src).is_err());
+    }
+}
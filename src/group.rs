@@ -0,0 +1,120 @@
+//! A grouped layout for decoding four values at a time.
+//!
+//! Like group varint, each group of four values starts with a header of
+//! their widths, followed by their bytes, so decoding reads two runs of
+//! bytes per group instead of two per value. A width is the number of
+//! big-endian bytes after bvarint's lead byte, 0 to 8, with 0 for the value
+//! 0. That needs 4 bits, so the header is 2 bytes, not group varint's 1.
+//!
+//! The last group may have fewer than four values. Its unused widths are 0.
+//! This layout is not compatible with back-to-back bvarints, and does not
+//! preserve order.
+
+use crate::batch::MAX_PREALLOC;
+use crate::byte_width;
+use std::convert::TryFrom;
+use std::io;
+
+/// Write `values` in groups of four, each a 2-byte header of widths
+/// followed by the values' bytes, to `w` in one `write_all`.
+pub fn write_bvarint_group4(values: &[u64], mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(values.len() / 4 * 2 + 2 + values.len() * 8);
+    for group in values.chunks(4) {
+        let start = buf.len();
+        buf.extend_from_slice(&[0, 0]);
+        let mut header = 0u16;
+        for (i, &v) in group.iter().enumerate() {
+            let width = byte_width(v);
+            header |= (width as u16) << (12 - 4 * i);
+            buf.extend_from_slice(&v.to_be_bytes()[8 - width..]);
+        }
+        buf[start..start + 2].copy_from_slice(&header.to_be_bytes());
+    }
+    w.write_all(&buf)
+}
+
+/// Read `count` values written by [`write_bvarint_group4`] from `r`.
+///
+/// A width above 8, or a non-zero width for a missing value in the last
+/// group, is `InvalidData`.
+pub fn read_bvarint_group4(mut r: impl io::Read, count: usize) -> io::Result<Vec<u64>> {
+    let mut values = Vec::with_capacity(count.min(MAX_PREALLOC));
+    let mut payload = [0; 40];
+    while values.len() < count {
+        let n = (count - values.len()).min(4);
+        let mut header = [0; 2];
+        r.read_exact(&mut header)?;
+        let header = u16::from_be_bytes(header);
+        let widths = [12, 8, 4, 0].map(|shift| (header >> shift) as usize & 0xf);
+        if widths.iter().any(|&w| w > 8) || widths[n..].iter().any(|&w| w != 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid group header {:#06x}", header),
+            ));
+        }
+        let total = widths.iter().sum();
+        r.read_exact(&mut payload[..total])?;
+        let mut pos = 0;
+        for &width in &widths[..n] {
+            // Load 8 bytes and shift out what belongs to later values, which
+            // avoids a variable length copy. `payload` has 8 bytes of slack.
+            let bytes = <[u8; 8]>::try_from(&payload[pos..pos + 8]).unwrap();
+            let v = u64::from_be_bytes(bytes)
+                .checked_shr(64 - 8 * width as u32)
+                .unwrap_or(0);
+            values.push(v);
+            pos += width;
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip(values: Vec<u64>) {
+        let mut buf = Vec::new();
+        write_bvarint_group4(&values, &mut buf).unwrap();
+        assert_eq!(read_bvarint_group4(&buf[..], values.len()).unwrap(), values);
+    }
+
+    #[test]
+    fn test_round_trip_manual() {
+        let values = interesting_values();
+        for n in 0..=9 {
+            check_round_trip(values[..n].to_vec());
+        }
+        check_round_trip(values);
+    }
+
+    #[test]
+    fn test_layout() {
+        let mut buf = Vec::new();
+        write_bvarint_group4(&[0, 0x12, 0x3456, u64::MAX, 7], &mut buf).unwrap();
+        let mut expected = vec![0x01, 0x28, 0x12, 0x34, 0x56];
+        expected.extend_from_slice(&[0xff; 8]);
+        expected.extend_from_slice(&[0x10, 0x00, 7]);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = read_bvarint_group4(&[0x90, 0x00][..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // The second width is set, but only one value is left.
+        let err = read_bvarint_group4(&[0x11, 0x00, 1, 2][..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_group4(&[0x20, 0x00, 1][..], 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = read_bvarint_group4(&[0x10, 0x00, 1][..], usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_quickcheck() {
+        quickcheck(check_round_trip as fn(Vec<u64>));
+    }
+}
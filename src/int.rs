@@ -0,0 +1,166 @@
+//! Integer types other than `u64`.
+
+use crate::DecodeError;
+use std::convert::TryFrom;
+use std::io;
+
+/// Encode anything that converts losslessly to `u64` and write it to `w`.
+///
+/// `usize` does not implement `Into<u64>`; use
+/// [`BvarintInt::write_bvarint`] for it instead.
+pub fn write_bvarint_from<T: Into<u64>>(v: T, w: impl io::Write) -> io::Result<()> {
+    crate::write_bvarint(v.into(), w)
+}
+
+/// Unsigned integers that can be written and read as bvarints.
+///
+/// All types share the `u64` encoding. Decoding a value that does not fit
+/// in the type is a `DecodeError::TypeOverflow`, with kind `InvalidData`.
+/// The `usize` implementation assumes `usize` is at most 64 bits wide.
+pub trait BvarintInt: Sized {
+    /// Encode `self` and write it to `w`.
+    fn write_bvarint(self, w: impl io::Write) -> io::Result<()>;
+
+    /// Read from `r` and return the decoded integer.
+    fn read_bvarint(r: impl io::Read) -> io::Result<Self>;
+}
+
+macro_rules! impl_bvarint_int {
+    ($($ty:ty),*) => {
+        $(
+            impl BvarintInt for $ty {
+                fn write_bvarint(self, w: impl io::Write) -> io::Result<()> {
+                    crate::write_bvarint(self as u64, w)
+                }
+
+                fn read_bvarint(r: impl io::Read) -> io::Result<Self> {
+                    let v = crate::read_bvarint(r)?;
+                    <$ty>::try_from(v).map_err(|_| {
+                        DecodeError::TypeOverflow {
+                            max: <$ty>::MAX as u64,
+                            got: v,
+                        }
+                        .into()
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_bvarint_int!(u8, u16, u32, u64, usize);
+
+/// Encode `v` as a `u64` and write it to `w`.
+pub fn write_bvarint_usize(v: usize, w: impl io::Write) -> io::Result<()> {
+    v.write_bvarint(w)
+}
+
+/// Read from `r` and return the decoded `usize`.
+///
+/// Values above `usize::MAX` on the current target, which can be written
+/// by a 64-bit peer and read on a 32-bit one, are `InvalidData` errors
+/// instead of being truncated.
+pub fn read_bvarint_usize(r: impl io::Read) -> io::Result<usize> {
+    usize::read_bvarint(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip<T: BvarintInt + Copy + PartialEq + std::fmt::Debug>(x: T) {
+        let mut buf = Vec::new();
+        x.write_bvarint(&mut buf).unwrap();
+        assert_eq!(T::read_bvarint(&buf[..]).unwrap(), x);
+    }
+
+    fn check_overflow<T: BvarintInt>(max: u64) {
+        let mut buf = Vec::new();
+        crate::write_bvarint(max, &mut buf).unwrap();
+        assert!(T::read_bvarint(&buf[..]).is_ok());
+        buf.clear();
+        crate::write_bvarint(max + 1, &mut buf).unwrap();
+        let err = T::read_bvarint(&buf[..]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_round_trip_boundaries() {
+        for &x in &[0, 0xf6, 0xf7, u8::MAX] {
+            check_round_trip(x);
+        }
+        for &x in &[0, 0x100, u16::MAX] {
+            check_round_trip(x);
+        }
+        for &x in &[0, 0x10000, u32::MAX] {
+            check_round_trip(x);
+        }
+        for &x in &[0, u64::MAX] {
+            check_round_trip(x);
+        }
+        for &x in &[0, usize::MAX] {
+            check_round_trip(x);
+        }
+    }
+
+    #[test]
+    fn test_write_from() {
+        let mut buf = Vec::new();
+        write_bvarint_from(0x1234u16, &mut buf).unwrap();
+        write_bvarint_from(7u8, &mut buf).unwrap();
+        write_bvarint_from(u32::MAX, &mut buf).unwrap();
+        let mut r = &buf[..];
+        assert_eq!(crate::read_bvarint(&mut r).unwrap(), 0x1234);
+        assert_eq!(crate::read_bvarint(&mut r).unwrap(), 7);
+        assert_eq!(crate::read_bvarint(&mut r).unwrap(), u32::MAX as u64);
+    }
+
+    #[test]
+    fn test_overflow() {
+        check_overflow::<u8>(u8::MAX as u64);
+        check_overflow::<u16>(u16::MAX as u64);
+        check_overflow::<u32>(u32::MAX as u64);
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let mut buf = Vec::new();
+        crate::write_bvarint(0x10000, &mut buf).unwrap();
+        let err = DecodeError::from(u16::read_bvarint(&buf[..]).unwrap_err());
+        assert!(matches!(
+            err,
+            DecodeError::TypeOverflow {
+                max: 0xffff,
+                got: 0x10000
+            }
+        ));
+        let err = DecodeError::from(u16::read_bvarint(&[0xff][..]).unwrap_err());
+        assert!(matches!(err, DecodeError::ReservedLeadByte { offset: 0 }));
+    }
+
+    #[test]
+    fn test_usize() {
+        let mut buf = Vec::new();
+        write_bvarint_usize(usize::MAX, &mut buf).unwrap();
+        assert_eq!(read_bvarint_usize(&buf[..]).unwrap(), usize::MAX);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn test_usize_overflow() {
+        check_overflow::<usize>(u32::MAX as u64);
+        let mut buf = Vec::new();
+        crate::write_bvarint(u32::MAX as u64 + 1, &mut buf).unwrap();
+        let err = read_bvarint_usize(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_round_trip_quickcheck() {
+        quickcheck(check_round_trip::<u8> as fn(u8));
+        quickcheck(check_round_trip::<u16> as fn(u16));
+        quickcheck(check_round_trip::<u32> as fn(u32));
+        quickcheck(check_round_trip::<u64> as fn(u64));
+    }
+}
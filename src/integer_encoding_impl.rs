@@ -0,0 +1,77 @@
+//! Transcoding from and to the `integer-encoding` crate's `VarInt`, for
+//! migrating data between the two formats.
+//!
+//! Only unsigned types are supported. The value is preserved, not the bytes.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use integer_encoding::VarInt;
+use integer_encoding::VarIntReader;
+use integer_encoding::VarIntWriter;
+use std::convert::TryFrom;
+use std::io;
+
+/// Read one `T` varint from `r` and write it as bvarint to `w`.
+///
+/// `integer-encoding` truncates varints that do not fit in `T`, so choose
+/// a `T` wide enough for the data.
+pub fn from_varint<T: VarInt + Into<u64>>(
+    mut r: impl io::Read,
+    w: impl io::Write,
+) -> io::Result<()> {
+    let v: T = r.read_varint()?;
+    write_bvarint(v.into(), w)
+}
+
+/// Read one bvarint value from `r` and write it as a `T` varint to `w`.
+///
+/// Values that do not fit in `T` are `InvalidData` errors.
+pub fn to_varint<T: VarInt + TryFrom<u64>>(
+    r: impl io::Read,
+    mut w: impl io::Write,
+) -> io::Result<()> {
+    let v = T::try_from(read_bvarint(r)?).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "value does not fit the varint type",
+        )
+    })?;
+    w.write_varint(v)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+
+    #[test]
+    fn test_round_trip() {
+        for x in interesting_values() {
+            let varint = x.encode_var_vec();
+            let mut bvarint = Vec::new();
+            from_varint::<u64>(&varint[..], &mut bvarint).unwrap();
+            assert_eq!(read_bvarint(&bvarint[..]).unwrap(), x);
+
+            let mut out = Vec::new();
+            to_varint::<u64>(&bvarint[..], &mut out).unwrap();
+            assert_eq!(out, varint);
+        }
+    }
+
+    #[test]
+    fn test_narrow_types() {
+        for &x in &[0u32, 0xf6, 0xf7, 0x1234, u32::MAX] {
+            let mut bvarint = Vec::new();
+            from_varint::<u32>(&x.encode_var_vec()[..], &mut bvarint).unwrap();
+            let mut out = Vec::new();
+            to_varint::<u32>(&bvarint[..], &mut out).unwrap();
+            assert_eq!(u32::decode_var(&out), Some((x, out.len())));
+        }
+
+        let mut bvarint = Vec::new();
+        write_bvarint(0x100, &mut bvarint).unwrap();
+        let err = to_varint::<u8>(&bvarint[..], Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
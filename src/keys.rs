@@ -0,0 +1,249 @@
+//! Encodings for sort keys.
+
+use crate::decode_bvarint;
+use crate::encode_bvarint;
+use crate::peek_len;
+use crate::read_bvarint;
+use crate::read_bvarint_pair;
+use crate::write_bvarint;
+use crate::write_bvarint_pair;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use std::io;
+
+/// Encode `v` so larger values sort first, and write it to `w`.
+///
+/// The bytes are the bitwise complement of the [`write_bvarint`] encoding.
+/// Since the encoding is prefix-free, complementing reverses the `memcmp`
+/// order, and small values stay small.
+///
+/// [`write_bvarint`]: crate::write_bvarint
+pub fn write_bvarint_desc(v: u64, mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = encode_bvarint(v, &mut buf).expect("9 bytes fit any u64");
+    for b in &mut buf[..len] {
+        *b = !*b;
+    }
+    w.write_all(&buf[..len])
+}
+
+/// Read a value written by [`write_bvarint_desc`] from `r`.
+pub fn read_bvarint_desc(mut r: impl io::Read) -> io::Result<u64> {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    r.read_exact(&mut buf[..1])?;
+    let len = peek_len(!buf[0]);
+    if len == 0 {
+        return Err(DecodeError::ReservedLeadByte { offset: 0 }.into());
+    }
+    r.read_exact(&mut buf[1..len])?;
+    for b in &mut buf[..len] {
+        *b = !*b;
+    }
+    let (v, _) = decode_bvarint(&buf[..len])?;
+    Ok(v)
+}
+
+/// Encode the composite key `(a, b)` and write it to `w`.
+///
+/// The bytes sort like the tuple: by `a`, then by `b`. This is `a` and `b`
+/// encoded back-to-back. The encoding of `a` is never a prefix of another
+/// encoding, so two keys with different `a` differ within `a`'s bytes and
+/// `b` only matters when `a` is equal.
+pub fn write_bvarint_key2(a: u64, b: u64, w: impl io::Write) -> io::Result<()> {
+    write_bvarint_pair(a, b, w)
+}
+
+/// Read a key written by [`write_bvarint_key2`] from `r`.
+pub fn read_bvarint_key2(r: impl io::Read) -> io::Result<(u64, u64)> {
+    read_bvarint_pair(r)
+}
+
+/// Encode `v` so the encoded bytes sort in IEEE 754 total order, and write
+/// it to `w`.
+///
+/// Positive values get the sign bit flipped, negative values get all bits
+/// flipped. The exact bit pattern round-trips. `-0.0` sorts before `0.0`.
+/// NaNs with the sign bit set sort before `-inf`, other NaNs sort after
+/// `inf`.
+pub fn write_bvarint_f64_ordered(v: f64, w: impl io::Write) -> io::Result<()> {
+    let bits = v.to_bits();
+    let mapped = if bits >> 63 == 0 {
+        bits ^ (1 << 63)
+    } else {
+        !bits
+    };
+    write_bvarint(mapped, w)
+}
+
+/// Read a value written by [`write_bvarint_f64_ordered`] from `r`.
+pub fn read_bvarint_f64_ordered(r: impl io::Read) -> io::Result<f64> {
+    let mapped = read_bvarint(r)?;
+    let bits = if mapped >> 63 == 1 {
+        mapped ^ (1 << 63)
+    } else {
+        !mapped
+    };
+    Ok(f64::from_bits(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn encode_desc(x: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_bvarint_desc(x, &mut buf).unwrap();
+        buf
+    }
+
+    fn check_round_trip_desc(x: u64) {
+        let buf = encode_desc(x);
+        assert_eq!(read_bvarint_desc(&buf[..]).unwrap(), x);
+    }
+
+    fn check_order_desc(x: u64, y: u64) {
+        let bufx = encode_desc(x);
+        let bufy = encode_desc(y);
+        assert_eq!(
+            y.cmp(&x),
+            bufx.cmp(&bufy),
+            "check_order_desc(0x{:x}, 0x{:x}) {:?} {:?}",
+            x,
+            y,
+            bufx,
+            bufy,
+        );
+    }
+
+    #[test]
+    fn test_round_trip_desc_manual() {
+        for x in interesting_values() {
+            check_round_trip_desc(x);
+        }
+        assert!(read_bvarint_desc(&[0x00][..]).is_err());
+    }
+
+    #[test]
+    fn test_order_desc_manual() {
+        let values = interesting_values();
+        for x in &values {
+            for y in &values {
+                check_order_desc(*x, *y);
+            }
+        }
+    }
+
+    fn encode_key2(a: u64, b: u64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_bvarint_key2(a, b, &mut buf).unwrap();
+        buf
+    }
+
+    fn check_key2(a1: u64, b1: u64, a2: u64, b2: u64) {
+        let buf1 = encode_key2(a1, b1);
+        let buf2 = encode_key2(a2, b2);
+        assert_eq!(
+            (a1, b1).cmp(&(a2, b2)),
+            buf1.cmp(&buf2),
+            "check_key2(0x{:x}, 0x{:x}, 0x{:x}, 0x{:x}) {:?} {:?}",
+            a1,
+            b1,
+            a2,
+            b2,
+            buf1,
+            buf2,
+        );
+        assert_eq!(read_bvarint_key2(&buf1[..]).unwrap(), (a1, b1));
+    }
+
+    #[test]
+    fn test_key2_width_boundaries() {
+        // The first fields differ in width, and the second fields would
+        // sort the other way if the first field did not delimit itself.
+        let firsts: Vec<u64> = (1..=8)
+            .map(|w| crate::value_range_for_width(w).unwrap())
+            .flat_map(|r| vec![*r.end() - 1, *r.end(), r.end().wrapping_add(1)])
+            .collect();
+        let seconds = [0, 0xf6, 0xf7, 0xffff, u64::MAX];
+        for &a1 in &firsts {
+            for &a2 in &firsts {
+                for &b1 in &seconds {
+                    for &b2 in &seconds {
+                        check_key2(a1, b1, a2, b2);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_key2_quickcheck() {
+        quickcheck(check_key2 as fn(u64, u64, u64, u64));
+        quickcheck((|a, b1, b2| check_key2(a, b1, a, b2)) as fn(u64, u64, u64));
+    }
+
+    fn encode_f64(x: f64) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_bvarint_f64_ordered(x, &mut buf).unwrap();
+        buf
+    }
+
+    fn interesting_values_f64() -> Vec<f64> {
+        vec![
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.5,
+            -1.0,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.0,
+            1.5,
+            f64::MAX,
+            f64::INFINITY,
+        ]
+    }
+
+    #[test]
+    fn test_round_trip_f64() {
+        let nan = f64::from_bits(0x7ff8_0000_0000_1234);
+        for x in interesting_values_f64().into_iter().chain(vec![nan, -nan]) {
+            let buf = encode_f64(x);
+            let y = read_bvarint_f64_ordered(&buf[..]).unwrap();
+            assert_eq!(x.to_bits(), y.to_bits());
+        }
+    }
+
+    #[test]
+    fn test_order_f64() {
+        // The values are listed in increasing order, with -0.0 before 0.0.
+        let values = interesting_values_f64();
+        for (i, x) in values.iter().enumerate() {
+            for (j, y) in values.iter().enumerate() {
+                assert_eq!(
+                    i.cmp(&j),
+                    encode_f64(*x).cmp(&encode_f64(*y)),
+                    "{} {}",
+                    x,
+                    y
+                );
+            }
+        }
+        let nan = f64::NAN;
+        assert!(encode_f64(nan) > encode_f64(f64::INFINITY));
+        assert!(encode_f64(-nan) < encode_f64(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_round_trip_desc_quickcheck() {
+        quickcheck(check_round_trip_desc as fn(u64));
+    }
+
+    #[test]
+    fn test_order_desc_quickcheck() {
+        quickcheck(check_order_desc as fn(u64, u64));
+    }
+}
@@ -0,0 +1,77 @@
+//! Little-endian variant for byte compatibility with existing formats.
+//!
+//! The lead byte is the same as [`write_bvarint`](crate::write_bvarint),
+//! but the bytes after it are little-endian. This is NOT order-preserving
+//! and should only be used where an existing format requires it.
+
+use crate::encoded_len;
+use crate::peek_len;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use std::io;
+
+/// Encode `v` with a little-endian tail and write it to `w`.
+pub fn write_bvarint_le(v: u64, mut w: impl io::Write) -> io::Result<()> {
+    let len = encoded_len(v);
+    if len == 1 {
+        return w.write_all(&[v as u8]);
+    }
+    let mut buf = [0; MAX_ENCODED_LEN];
+    buf[0] = (0xf7 - 2 + len) as u8;
+    buf[1..len].copy_from_slice(&v.to_le_bytes()[..(len - 1)]);
+    w.write_all(&buf[..len])
+}
+
+/// Read a value written by [`write_bvarint_le`] from `r`.
+pub fn read_bvarint_le(mut r: impl io::Read) -> io::Result<u64> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    match peek_len(lead[0]) {
+        0 => Err(DecodeError::ReservedLeadByte { offset: 0 }.into()),
+        1 => Ok(lead[0] as _),
+        len => {
+            let mut a = [0; 8];
+            r.read_exact(&mut a[..(len - 1)])?;
+            Ok(u64::from_le_bytes(a))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip(x: u64) {
+        let mut buf = Vec::new();
+        write_bvarint_le(x, &mut buf).unwrap();
+        assert_eq!(buf.len(), encoded_len(x));
+        assert_eq!(
+            read_bvarint_le(&buf[..]).unwrap(),
+            x,
+            "check_round_trip(0x{:x})",
+            x
+        );
+    }
+
+    #[test]
+    fn test_le_bytes() {
+        let mut buf = Vec::new();
+        write_bvarint_le(0x123456, &mut buf).unwrap();
+        assert_eq!(buf, [0xf9, 0x56, 0x34, 0x12]);
+        assert!(read_bvarint_le(&[0xff][..]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_manual() {
+        for x in interesting_values() {
+            check_round_trip(x);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_quickcheck() {
+        quickcheck(check_round_trip as fn(u64));
+    }
+}
@@ -0,0 +1,116 @@
+//! Transcoding from and to unsigned LEB128.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use std::io;
+
+fn write_leb128(mut v: u64, mut w: impl io::Write) -> io::Result<()> {
+    let mut buf = [0; 10];
+    let mut len = 0;
+    loop {
+        let b = (v & 0x7f) as u8;
+        v >>= 7;
+        if v == 0 {
+            buf[len] = b;
+            len += 1;
+            break;
+        }
+        buf[len] = b | 0x80;
+        len += 1;
+    }
+    w.write_all(&buf[..len])
+}
+
+fn read_leb128(mut r: impl io::Read) -> io::Result<u64> {
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut b = [0];
+        r.read_exact(&mut b)?;
+        let bits = (b[0] & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            break;
+        }
+        v |= bits << shift;
+        if b[0] & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "LEB128 value exceeds u64::MAX",
+    ))
+}
+
+/// Read one unsigned LEB128 value from `r` and write it as bvarint to `w`.
+pub fn leb128_to_bvarint(r: impl io::Read, w: impl io::Write) -> io::Result<()> {
+    write_bvarint(read_leb128(r)?, w)
+}
+
+/// Read one bvarint value from `r` and write it as unsigned LEB128 to `w`.
+pub fn bvarint_to_leb128(r: impl io::Read, w: impl io::Write) -> io::Result<()> {
+    write_leb128(read_bvarint(r)?, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip(x: u64) {
+        let mut bvarint = Vec::new();
+        write_bvarint(x, &mut bvarint).unwrap();
+        let mut leb128 = Vec::new();
+        bvarint_to_leb128(&bvarint[..], &mut leb128).unwrap();
+        assert_eq!(read_leb128(&leb128[..]).unwrap(), x);
+        let mut back = Vec::new();
+        leb128_to_bvarint(&leb128[..], &mut back).unwrap();
+        assert_eq!(back, bvarint, "check_round_trip(0x{:x})", x);
+    }
+
+    #[test]
+    fn test_leb128_known() {
+        // From the DWARF specification.
+        for &(v, bytes) in &[
+            (2, &[2][..]),
+            (127, &[127]),
+            (128, &[0x80, 1]),
+            (129, &[0x81, 1]),
+            (130, &[0x82, 1]),
+            (12857, &[0xb9, 100]),
+        ] {
+            let mut buf = Vec::new();
+            write_leb128(v, &mut buf).unwrap();
+            assert_eq!(buf, bytes);
+            assert_eq!(read_leb128(bytes).unwrap(), v);
+        }
+        let mut buf = Vec::new();
+        write_leb128(u64::MAX, &mut buf).unwrap();
+        assert_eq!(buf.len(), 10);
+        assert_eq!(buf[9], 1);
+    }
+
+    #[test]
+    fn test_leb128_overflow() {
+        let mut buf = vec![0xff; 9];
+        buf.push(2);
+        let err = read_leb128(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_leb128(&[0x80; 11][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_leb128(&[0x80][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_manual() {
+        for x in interesting_values() {
+            check_round_trip(x);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_quickcheck() {
+        quickcheck(check_round_trip as fn(u64));
+    }
+}
@@ -5,49 +5,792 @@
 //!
 //! Changed so leading 0xff is reserved for larger integers
 //! and some branches are removed.
+//!
+//! The crate is `no_std` without the default `std` feature. The slice-based
+//! functions are always available. The `io::Read` / `io::Write` functions
+//! require `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
 use std::io;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod blob;
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "std")]
+mod checked;
+mod debug;
+#[cfg(feature = "std")]
+mod derive;
+mod encoded;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod fixed;
+#[cfg(feature = "std")]
+mod flags;
+#[cfg(feature = "codec")]
+mod framed;
+#[cfg(feature = "std")]
+mod group;
+#[cfg(feature = "std")]
+mod int;
+#[cfg(feature = "integer-encoding")]
+mod integer_encoding_impl;
+#[cfg(feature = "std")]
+mod keys;
+#[cfg(feature = "std")]
+mod le;
+#[cfg(feature = "std")]
+mod leb128;
+#[cfg(all(feature = "std", any(unix, windows)))]
+mod positioned;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod signed;
+mod slice_iter;
+#[cfg(feature = "std")]
+mod small;
+#[cfg(feature = "smallvec")]
+mod smallvec_impl;
+mod state;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(feature = "std")]
+mod varint_codec;
+#[cfg(feature = "alloc")]
+mod vec;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod wide;
+
+#[cfg(feature = "tokio")]
+pub use async_io::{read_bvarint_async, write_bvarint_async};
+#[cfg(feature = "std")]
+pub use batch::{
+    encode_many, read_bvarint_array, read_bvarint_block_adaptive, read_bvarint_column,
+    read_bvarint_deltas, read_bvarint_exact_count, read_bvarint_map, read_bvarint_pair,
+    read_bvarint_rle, read_bvarint_vec, reencode_stream, transform_stream,
+    write_bvarint_block_adaptive, write_bvarint_column, write_bvarint_deltas, write_bvarint_map,
+    write_bvarint_pair, write_bvarint_rle, write_bvarint_slice,
+};
+#[cfg(feature = "std")]
+pub use blob::{read_bvarint_prefixed_bytes, write_bvarint_prefixed_bytes, BvarintChunkWriter};
+#[cfg(feature = "bytes")]
+pub use buf::{get_bvarint, put_bvarint};
+#[cfg(feature = "derive")]
+pub use bvarint_derive::{BvarintDecode, BvarintEncode};
+#[cfg(feature = "std")]
+pub use checked::{read_bvarint_checked, write_bvarint_checked};
+#[cfg(feature = "alloc")]
+pub use debug::debug_encoding;
+pub use debug::HexEncoded;
+#[cfg(feature = "std")]
+pub use derive::{BvarintDecode, BvarintEncode};
+pub use encoded::EncodedU64;
+pub use error::DecodeError;
+pub use error::EncodeError;
+pub use fixed::patch_bvarint_fixed;
+#[cfg(feature = "alloc")]
+pub use fixed::{patch_bvarint_slot, reserve_bvarint_slot, SlotHandle};
+#[cfg(feature = "std")]
+pub use fixed::{read_bvarint_fixed, write_bvarint_fixed};
+#[cfg(feature = "std")]
+pub use flags::{read_bvarint_flags, write_bvarint_flags, FlagSet};
+#[cfg(feature = "codec")]
+pub use framed::BvarintLengthDelimitedCodec;
+#[cfg(feature = "std")]
+pub use group::{read_bvarint_group4, write_bvarint_group4};
+#[cfg(feature = "std")]
+pub use int::{read_bvarint_usize, write_bvarint_from, write_bvarint_usize, BvarintInt};
+#[cfg(feature = "integer-encoding")]
+pub use integer_encoding_impl::{from_varint, to_varint};
+#[cfg(feature = "std")]
+pub use keys::{
+    read_bvarint_desc, read_bvarint_f64_ordered, read_bvarint_key2, write_bvarint_desc,
+    write_bvarint_f64_ordered, write_bvarint_key2,
+};
+#[cfg(feature = "std")]
+pub use le::{read_bvarint_le, write_bvarint_le};
+#[cfg(feature = "std")]
+pub use leb128::{bvarint_to_leb128, leb128_to_bvarint};
+#[cfg(all(feature = "std", any(unix, windows)))]
+pub use positioned::write_bvarint_at;
+#[cfg(feature = "std")]
+pub use signed::{
+    read_bvarint_i64, read_bvarint_i64_ordered, read_bvarint_rebased, write_bvarint_i64,
+    write_bvarint_i64_ordered, write_bvarint_rebased, zigzag_decode, zigzag_encode,
+};
+pub use slice_iter::{fold_bvarints, BvarintSliceIter};
+#[cfg(feature = "std")]
+pub use small::{
+    read_bvarint_bool, read_bvarint_enum, read_bvarint_option, read_bvarint_tagged,
+    write_bvarint_bool, write_bvarint_option, write_bvarint_tagged,
+};
+pub use state::BvarintDecodeState;
+#[cfg(feature = "alloc")]
+pub use state::ResumableDecoder;
+pub use stats::{format_stats, Stats};
+#[cfg(feature = "std")]
+pub use stream::{
+    read_bvarint_bufread, read_bvarint_opt, BvarintReader, BvarintSink, BvarintSource,
+    BvarintWriter, IndexedBvarintWriter, PeekableBvarintReader, StatReader,
+};
+#[cfg(feature = "std")]
+pub use varint_codec::VarintCodec;
+#[cfg(feature = "alloc")]
+pub use vec::{
+    decode_all_from_slice, decode_all_into, encode_all_to_vec, push_bvarint_deque,
+    read_bvarint_deque, shrink_to_canonical, BvarintVecExt,
+};
+#[cfg(feature = "std")]
+pub use wide::{
+    read_bvarint_auto, read_bvarint_bigint, read_bvarint_or_overflow, read_bvarint_u128,
+    write_bvarint_auto, write_bvarint_bytes_bigint, write_bvarint_saturating_u128,
+    write_bvarint_u128, BvarintConfig,
+};
+
+/// The largest value encoded in 1 byte. Values up to this are encoded as
+/// themselves.
+pub const ONE_BYTE_MAX: u64 = 0xf6;
+
+/// The largest value encoded in 2 bytes: lead byte `0xf7` and 1 byte.
+pub const TWO_BYTE_MAX: u64 = 0xff;
+
+/// The largest value encoded in 3 bytes: lead byte `0xf8` and 2 bytes.
+pub const THREE_BYTE_MAX: u64 = 0xffff;
+
 /// Encode `v` and write it to `w`.
+///
+/// The encoding is built on the stack and written with a single
+/// `write_all`, so an unbuffered `w` sees one `write` call per value.
+///
+/// If `w` fails partway, the error is returned, but a prefix of the
+/// encoding may already have been written. `w` then ends with a truncated
+/// value that readers report as `UnexpectedEof`.
+#[cfg(feature = "std")]
+#[inline]
 pub fn write_bvarint(v: u64, mut w: impl io::Write) -> io::Result<()> {
     match v {
-        0..=0xf6 => {
-            w.write_all(&[v as u8])?;
+        0..=ONE_BYTE_MAX => w.write_all(&[v as u8]),
+        _ => {
+            // v > ONE_BYTE_MAX, so width is in 1..=8. Shift out the
+            // leading zero bytes so the copy has a fixed size.
+            let width = byte_width(v);
+            let mut buf = [0; MAX_ENCODED_LEN];
+            buf[0] = (0xf7 - 1 + width) as u8;
+            buf[1..].copy_from_slice(&(v << (8 * (8 - width))).to_be_bytes());
+            w.write_all(&buf[..width + 1])
         }
-        0xf7..=u64::MAX => {
-            let width = ((64 + 8 - 1 - v.leading_zeros()) / 8) as usize;
-            debug_assert!(width >= 1);
-            let a: [u8; 8] = v.to_be_bytes();
-            w.write_all(&[(0xf7 - 1 + width) as u8])?;
-            w.write_all(&a[(8 - width)..])?;
+    }
+}
+
+/// Encode `v` and write it to `w`, passing the lead byte and the
+/// big-endian bytes as two slices to one `write_vectored` call.
+///
+/// Values up to [`ONE_BYTE_MAX`] are written with `write_all`. If `w` takes
+/// only part of the slices, the rest is written with `write_all`.
+#[cfg(feature = "std")]
+pub fn write_bvarint_vectored(v: u64, mut w: impl io::Write) -> io::Result<()> {
+    if v <= ONE_BYTE_MAX {
+        return w.write_all(&[v as u8]);
+    }
+    let width = byte_width(v);
+    let lead = [(0xf7 - 1 + width) as u8];
+    let bytes = v.to_be_bytes();
+    let payload = &bytes[8 - width..];
+    let slices = [io::IoSlice::new(&lead), io::IoSlice::new(payload)];
+    let n = loop {
+        match w.write_vectored(&slices) {
+            Ok(n) => break n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
+    };
+    if n == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            "failed to write whole buffer",
+        ));
     }
+    w.write_all(&payload[n - 1..])
+}
+
+/// Encode `v` and write it to `w`. Return the number of bytes written.
+#[cfg(feature = "std")]
+#[inline]
+pub fn write_bvarint_counted(v: u64, mut w: impl io::Write) -> io::Result<usize> {
+    let (buf, len) = encode_to_stack(v);
+    w.write_all(&buf[..len])?;
+    Ok(len)
+}
+
+/// The maximum number of bytes of an encoded `u64`.
+pub const MAX_ENCODED_LEN: usize = 9;
+
+/// Return the number of bytes `write_bvarint` would emit for `v`.
+#[inline]
+pub const fn encoded_len(v: u64) -> usize {
+    match v {
+        0..=ONE_BYTE_MAX => 1,
+        _ => byte_width(v) + 1,
+    }
+}
+
+/// Return the number of bytes in the big-endian form of `v` without
+/// leading zero bytes. This is 0 for 0.
+#[inline]
+pub(crate) const fn byte_width(v: u64) -> usize {
+    let bits = 64 - v.leading_zeros();
+    bits.div_ceil(8) as usize
+}
+
+/// Return the values that encode to exactly `width` bytes, or `None` if
+/// `width` is not in `1..=9`.
+pub const fn value_range_for_width(width: usize) -> Option<core::ops::RangeInclusive<u64>> {
+    match width {
+        1 => Some(0..=ONE_BYTE_MAX),
+        2 => Some(ONE_BYTE_MAX + 1..=TWO_BYTE_MAX),
+        3..=8 => Some(1 << (8 * (width - 2))..=(1 << (8 * (width - 1))) - 1),
+        9 => Some(1 << 56..=u64::MAX),
+        _ => None,
+    }
+}
+
+/// Return the total encoded length of a value given its lead byte.
+///
+/// Return 0 for `0xff`, which is reserved and whose length cannot be
+/// determined from the lead byte alone.
+#[inline]
+pub const fn peek_len(first_byte: u8) -> usize {
+    match first_byte {
+        0..=0xf6 => 1,
+        0xf7..=0xfe => (first_byte - 0xf7) as usize + 2,
+        0xff => 0,
+    }
+}
+
+/// Encode `v` into the front of `buf` and return the number of bytes used.
+///
+/// If `buf` is too small, nothing is written and the error reports how many
+/// bytes are needed.
+///
+/// This is a `const fn`, so encodings can be computed at compile time:
+///
+/// ```
+/// use bvarint::{encode_bvarint, MAX_ENCODED_LEN};
+///
+/// const TAG: [u8; MAX_ENCODED_LEN] = {
+///     let mut buf = [0; MAX_ENCODED_LEN];
+///     assert!(encode_bvarint(0x1234, &mut buf).is_ok());
+///     buf
+/// };
+/// assert_eq!(TAG[..3], [0xf8, 0x12, 0x34]);
+/// ```
+#[inline]
+pub const fn encode_bvarint(v: u64, buf: &mut [u8]) -> Result<usize, EncodeError> {
+    let len = encoded_len(v);
+    if buf.len() < len {
+        return Err(EncodeError::BufferTooSmall { needed: len });
+    }
+    if len == 1 {
+        buf[0] = v as u8;
+    } else {
+        let width = len - 1;
+        let a: [u8; 8] = v.to_be_bytes();
+        buf[0] = (0xf7 - 1 + width) as u8;
+        let mut i = 0;
+        while i < width {
+            buf[1 + i] = a[8 - width + i];
+            i += 1;
+        }
+    }
+    Ok(len)
+}
+
+/// Encode `v` into a stack buffer.
+///
+/// Return the buffer and the number of valid leading bytes, so the
+/// encoding is `&buf[..len]`.
+#[inline]
+pub const fn encode_to_stack(v: u64) -> ([u8; MAX_ENCODED_LEN], usize) {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    let len = match encode_bvarint(v, &mut buf) {
+        Ok(len) => len,
+        Err(_) => unreachable!(),
+    };
+    (buf, len)
+}
+
+/// Return the encoded bytes of `v` as an iterator, to `chain` with other
+/// bytes without an intermediate buffer.
+#[inline]
+pub fn bvarint_bytes(v: u64) -> impl ExactSizeIterator<Item = u8> + Clone {
+    let (buf, len) = encode_to_stack(v);
+    IntoIterator::into_iter(buf).take(len)
+}
+
+/// `no_panic` does not support `const fn`, so check [`encode_bvarint`]
+/// through a wrapper it gets inlined into.
+#[cfg(bvarint_no_panic)]
+#[no_panic::no_panic]
+fn encode_bvarint_no_panic(v: u64, buf: &mut [u8]) -> Result<usize, EncodeError> {
+    encode_bvarint(v, buf)
+}
+
+/// Encode `v` into the front of `buf` and advance `buf` past it.
+///
+/// If `buf` is too small, nothing is written and `buf` is unchanged.
+#[inline]
+pub fn write_bvarint_into(v: u64, buf: &mut &mut [u8]) -> Result<(), EncodeError> {
+    let len = encode_bvarint(v, buf)?;
+    let rest = core::mem::take(buf);
+    *buf = &mut rest[len..];
     Ok(())
 }
 
+/// Feed the encoding of `v` to `h`, as a single `write` of the encoded
+/// bytes.
+///
+/// Unlike `v.hash(h)`, this hashes the canonical bytes, so the result
+/// matches hashing the output of [`write_bvarint`] and does not depend on
+/// the platform.
+#[inline]
+pub fn hash_bvarint(v: u64, h: &mut impl core::hash::Hasher) {
+    let (buf, len) = encode_to_stack(v);
+    h.write(&buf[..len]);
+}
+
 /// Read from `r` and return the decoded integer.
+///
+/// Use `DecodeError::from` on the returned error to match on the reason.
+#[cfg(feature = "std")]
+#[inline]
 pub fn read_bvarint(mut r: impl io::Read) -> io::Result<u64> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    read_bvarint_after_lead(lead[0], r)
+}
+
+/// Read from `r` and return the decoded integer, using `scratch` for the
+/// bytes after the lead byte.
+///
+/// `scratch` does not need to be zeroed between calls.
+#[cfg(feature = "std")]
+#[inline]
+pub fn read_bvarint_with_buf(mut r: impl io::Read, scratch: &mut [u8; 8]) -> io::Result<u64> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    match lead[0] {
+        0..=0xf6 => Ok(lead[0] as _),
+        0xf7..=0xfe => {
+            let tail = &mut scratch[..(lead[0] - 0xf7 + 1) as usize];
+            r.read_exact(tail)?;
+            Ok(tail.iter().fold(0, |v, &b| (v << 8) | b as u64))
+        }
+        0xff => Err(overflow_error()),
+    }
+}
+
+/// Read from `r` and return the decoded integer, with the bytes it was
+/// decoded from and their length.
+///
+/// The bytes are kept verbatim, including overlong encodings, so they can be
+/// forwarded without re-encoding.
+#[cfg(feature = "std")]
+pub fn read_bvarint_with_bytes(
+    mut r: impl io::Read,
+) -> io::Result<(u64, [u8; MAX_ENCODED_LEN], usize)> {
+    let mut buf = [0; MAX_ENCODED_LEN];
+    r.read_exact(&mut buf[..1])?;
+    let len = peek_len(buf[0]);
+    if len == 0 {
+        return Err(overflow_error());
+    }
+    r.read_exact(&mut buf[1..len])?;
+    let (v, _) = decode_bvarint(&buf[..len])?;
+    Ok((v, buf, len))
+}
+
+/// Read the rest of a value whose lead byte was already consumed.
+#[cfg(feature = "std")]
+#[inline]
+pub(crate) fn read_bvarint_after_lead(lead: u8, mut r: impl io::Read) -> io::Result<u64> {
     let mut a = [0; 8];
-    r.read_exact(&mut a[7..8])?;
-    match a[7] {
-        0..=0xf6 => Ok(a[7] as _),
+    match lead {
+        0..=0xf6 => Ok(lead as _),
         0xf7..=0xfe => {
-            let width = (a[7] - 0xf7 + 1) as usize;
+            let width = (lead - 0xf7 + 1) as usize;
             r.read_exact(&mut a[(8 - width)..8])?;
             Ok(u64::from_be_bytes(a))
         }
         // 0xff is reserved for larger integers (ex. u128).
-        0xff => Err(io::Error::new(
+        0xff => Err(overflow_error()),
+    }
+}
+
+#[cfg(feature = "std")]
+fn overflow_error() -> io::Error {
+    DecodeError::ReservedLeadByte { offset: 0 }.into()
+}
+
+/// Read from `r` and return the decoded integer, reading at most
+/// `max_bytes` bytes including the lead byte.
+///
+/// If the lead byte implies a longer encoding, `DecodeError::LimitExceeded`
+/// is returned without reading further.
+#[cfg(feature = "std")]
+pub fn read_bvarint_limited(mut r: impl io::Read, max_bytes: usize) -> io::Result<u64> {
+    let limit_error = |needed| DecodeError::LimitExceeded {
+        needed,
+        limit: max_bytes,
+    };
+    if max_bytes == 0 {
+        return Err(limit_error(1).into());
+    }
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    let len = peek_len(lead[0]);
+    if len > max_bytes {
+        return Err(limit_error(len).into());
+    }
+    read_bvarint_after_lead(lead[0], r)
+}
+
+/// Read from `r` and return the decoded integer, charging its encoded
+/// length to `budget`.
+///
+/// Share one `budget` across the values of a message to bound the total
+/// bytes read, however many values it claims to have. If the lead byte
+/// implies more bytes than are left, `DecodeError::LimitExceeded` is
+/// returned without reading further, and `budget` is unchanged.
+#[cfg(feature = "std")]
+pub fn read_bvarint_budgeted(mut r: impl io::Read, budget: &mut u64) -> io::Result<u64> {
+    let limit = (*budget).min(usize::MAX as u64) as usize;
+    let limit_error = |needed| DecodeError::LimitExceeded { needed, limit };
+    if limit == 0 {
+        return Err(limit_error(1).into());
+    }
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    let len = peek_len(lead[0]);
+    if len > limit {
+        return Err(limit_error(len).into());
+    }
+    let v = read_bvarint_after_lead(lead[0], r)?;
+    *budget -= len as u64;
+    Ok(v)
+}
+
+/// Read from `r` and return the decoded integer, and whether its encoding
+/// was overlong.
+///
+/// Like the other decoders, this accepts a wider form than needed, such as
+/// `[0xf7, 0x01]` for 1. The flag is `true` for those, so callers can
+/// accept such input from other encoders while noticing it.
+#[cfg(feature = "std")]
+pub fn read_bvarint_lenient(mut r: impl io::Read) -> io::Result<(u64, bool)> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    let v = read_bvarint_after_lead(lead[0], r)?;
+    Ok((v, encoded_len(v) != peek_len(lead[0])))
+}
+
+/// Read from `r` and return the decoded integer, rejecting overlong
+/// encodings.
+///
+/// This is the streaming counterpart of [`validate_bvarint`]. An overlong
+/// encoding is reported as `DecodeError::NonCanonical`, with kind
+/// `InvalidData`.
+#[cfg(feature = "std")]
+pub fn read_bvarint_canonical(r: impl io::Read) -> io::Result<u64> {
+    let (v, overlong) = read_bvarint_lenient(r)?;
+    if overlong {
+        return Err(DecodeError::NonCanonical.into());
+    }
+    Ok(v)
+}
+
+/// Read from `r` and return the decoded integer if it is at most `max`.
+///
+/// Larger values are reported as `InvalidData`.
+#[cfg(feature = "std")]
+pub fn read_bvarint_bounded(r: impl io::Read, max: u64) -> io::Result<u64> {
+    let v = read_bvarint(r)?;
+    if v > max {
+        return Err(io::Error::new(
             io::ErrorKind::InvalidData,
-            "exceeds u64::MAX",
-        )),
+            format!("value {} exceeds max {}", v, max),
+        ));
+    }
+    Ok(v)
+}
+
+/// Read from `r` and return the decoded integer, which must not be 0.
+///
+/// Zero is reported as `InvalidData`.
+#[cfg(feature = "std")]
+pub fn read_bvarint_nonzero(r: impl io::Read) -> io::Result<core::num::NonZeroU64> {
+    let v = read_bvarint(r)?;
+    core::num::NonZeroU64::new(v)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "value is zero"))
+}
+
+/// Read past a value from `r` without decoding it.
+///
+/// Return the number of bytes skipped.
+#[cfg(feature = "std")]
+pub fn skip_bvarint(mut r: impl io::Read) -> io::Result<usize> {
+    let mut a = [0; 9];
+    r.read_exact(&mut a[..1])?;
+    let len = peek_len(a[0]);
+    if len == 0 {
+        return Err(overflow_error());
+    }
+    r.read_exact(&mut a[1..len])?;
+    Ok(len)
+}
+
+/// Decode an integer from the front of `buf`.
+///
+/// Return the decoded integer and the number of bytes consumed.
+#[cfg_attr(bvarint_no_panic, no_panic::no_panic)]
+#[inline]
+pub fn decode_bvarint(buf: &[u8]) -> Result<(u64, usize), DecodeError> {
+    let lead = *buf
+        .first()
+        .ok_or(DecodeError::UnexpectedEof { offset: 0 })?;
+    // A lookup table for the lead byte was measured slower than this match
+    // (see `lead_dispatch` in benches/bvarint.rs).
+    match lead {
+        0..=0xf6 => Ok((lead as _, 1)),
+        0xf7..=0xfe => {
+            let width = (lead - 0xf7 + 1) as usize;
+            let len = width + 1;
+            let tail = buf
+                .get(1..len)
+                .ok_or(DecodeError::UnexpectedEof { offset: 0 })?;
+            let mut a = [0; 8];
+            a[(8 - width)..].copy_from_slice(tail);
+            Ok((u64::from_be_bytes(a), len))
+        }
+        0xff => Err(DecodeError::ReservedLeadByte { offset: 0 }),
+    }
+}
+
+/// Decode an integer from the front of `buf` in a `const` context.
+///
+/// Bytes after the value are ignored. Panics if `buf` does not start with
+/// a complete encoding, which is a compile error when evaluated in a
+/// `const`, so fixed encodings can be checked at build time:
+///
+/// ```
+/// use bvarint::decode_bvarint_const;
+///
+/// const TAG_BYTES: [u8; 3] = [0xf8, 0x12, 0x34];
+/// const TAG: u64 = decode_bvarint_const(&TAG_BYTES);
+/// assert_eq!(TAG, 0x1234);
+/// ```
+pub const fn decode_bvarint_const(buf: &[u8]) -> u64 {
+    assert!(!buf.is_empty(), "decode_bvarint_const: empty input");
+    let lead = buf[0];
+    if lead <= 0xf6 {
+        return lead as u64;
+    }
+    assert!(
+        lead != 0xff,
+        "decode_bvarint_const: reserved lead byte 0xff"
+    );
+    let len = peek_len(lead);
+    assert!(buf.len() >= len, "decode_bvarint_const: truncated input");
+    let mut v = 0;
+    let mut i = 1;
+    while i < len {
+        v = (v << 8) | buf[i] as u64;
+        i += 1;
+    }
+    v
+}
+
+/// Decode an integer from the front of `buf` without bounds checks.
+///
+/// Return the decoded integer and the number of bytes consumed.
+///
+/// # Safety
+///
+/// `buf` must start with a complete encoding whose lead byte is not `0xff`,
+/// for example after [`decode_bvarint`] or [`validate_bvarint`] succeeded on
+/// it, or after checking `buf.len() >= peek_len(buf[0]) > 0`. Debug builds
+/// assert this.
+#[inline]
+pub unsafe fn decode_bvarint_unchecked(buf: &[u8]) -> (u64, usize) {
+    debug_assert!(!buf.is_empty(), "decode_bvarint_unchecked: empty input");
+    let lead = *buf.get_unchecked(0);
+    let len = peek_len(lead);
+    debug_assert!(
+        len > 0 && len <= buf.len(),
+        "decode_bvarint_unchecked: invalid or truncated input"
+    );
+    if len == 1 {
+        return (lead as _, 1);
+    }
+    let width = len - 1;
+    let mut a = [0; 8];
+    a.get_unchecked_mut((8 - width)..)
+        .copy_from_slice(buf.get_unchecked(1..len));
+    (u64::from_be_bytes(a), len)
+}
+
+/// Check that `buf` starts with a complete, canonical encoding.
+///
+/// The decoders accept overlong encodings like `[0xf7, 0x01]` for 1, while
+/// [`write_bvarint`] always emits the shortest form. This rejects the
+/// former so each value has exactly one accepted encoding.
+///
+/// Return the length of the encoding.
+pub fn validate_bvarint(buf: &[u8]) -> Result<usize, DecodeError> {
+    let (v, len) = decode_bvarint(buf)?;
+    if encoded_len(v) != len {
+        return Err(DecodeError::NonCanonical);
     }
+    Ok(len)
 }
 
-#[cfg(test)]
+/// Decode an integer that occupies all of `buf`.
+///
+/// Unlike [`decode_bvarint`], bytes after the value are an error.
+#[inline]
+pub fn read_bvarint_exact(buf: &[u8]) -> Result<u64, DecodeError> {
+    let (v, len) = decode_bvarint(buf)?;
+    if len < buf.len() {
+        return Err(DecodeError::TrailingBytes {
+            consumed: len,
+            remaining: buf.len() - len,
+        });
+    }
+    Ok(v)
+}
+
+/// Count the values in `buf` by walking the lead bytes, without decoding
+/// them.
+///
+/// Error if `buf` ends in the middle of a value.
+pub fn count_values(buf: &[u8]) -> Result<usize, DecodeError> {
+    let mut pos = 0;
+    let mut count = 0;
+    while let Some(&lead) = buf.get(pos) {
+        let len = peek_len(lead);
+        if len == 0 {
+            return Err(DecodeError::ReservedLeadByte { offset: pos });
+        }
+        if buf.len() - pos < len {
+            return Err(DecodeError::UnexpectedEof { offset: pos });
+        }
+        pos += len;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Compare two encodings as integers without decoding them.
+///
+/// Because the encoding is order-preserving, this is `a.cmp(b)`. Both
+/// inputs are checked to be exactly one canonical encoding, since overlong
+/// encodings do not sort by value.
+pub fn cmp_encoded(a: &[u8], b: &[u8]) -> Result<core::cmp::Ordering, DecodeError> {
+    validate_bvarint_exact(a)?;
+    validate_bvarint_exact(b)?;
+    Ok(a.cmp(b))
+}
+
+/// Check that `buf` is exactly one canonical encoding.
+pub(crate) fn validate_bvarint_exact(buf: &[u8]) -> Result<(), DecodeError> {
+    let len = validate_bvarint(buf)?;
+    if len < buf.len() {
+        return Err(DecodeError::TrailingBytes {
+            consumed: len,
+            remaining: buf.len() - len,
+        });
+    }
+    Ok(())
+}
+
+/// Decode an integer from `buf` starting at `*pos`, and advance `*pos` past
+/// it.
+///
+/// On error, `*pos` is unchanged.
+#[inline]
+pub fn decode_bvarint_from_cursor(buf: &[u8], pos: &mut usize) -> Result<u64, DecodeError> {
+    let rest = buf
+        .get(*pos..)
+        .ok_or(DecodeError::UnexpectedEof { offset: *pos })?;
+    let (v, len) = decode_bvarint(rest).map_err(|e| e.with_base_offset(*pos))?;
+    *pos += len;
+    Ok(v)
+}
+
+/// Decode an integer from the front of `buf` into `*out`, and return the
+/// number of bytes consumed.
+///
+/// On error, `*out` is unchanged.
+#[inline]
+pub fn decode_bvarint_out(buf: &[u8], out: &mut u64) -> Result<usize, DecodeError> {
+    let (v, len) = decode_bvarint(buf)?;
+    *out = v;
+    Ok(len)
+}
+
+/// Decode an integer by pulling exactly its encoded bytes from `it`.
+pub fn decode_bvarint_iter(it: &mut impl Iterator<Item = u8>) -> Result<u64, DecodeError> {
+    let lead = it.next().ok_or(DecodeError::UnexpectedEof { offset: 0 })?;
+    match lead {
+        0..=0xf6 => Ok(lead as _),
+        0xf7..=0xfe => {
+            let width = (lead - 0xf7 + 1) as usize;
+            let mut a = [0; 8];
+            for b in &mut a[(8 - width)..] {
+                *b = it.next().ok_or(DecodeError::UnexpectedEof { offset: 0 })?;
+            }
+            Ok(u64::from_be_bytes(a))
+        }
+        0xff => Err(DecodeError::ReservedLeadByte { offset: 0 }),
+    }
+}
+
+/// A `u64` that is serialized as its bvarint encoding with the `serde`
+/// feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bvarint(pub u64);
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
-    use quickcheck::quickcheck;
+
+    /// Run a property test, with few cases under Miri where each case is
+    /// slow.
+    pub(crate) fn quickcheck<A: quickcheck::Testable>(f: A) {
+        let mut qc = quickcheck::QuickCheck::new();
+        if cfg!(miri) {
+            qc = qc.tests(2);
+        }
+        qc.quickcheck(f)
+    }
 
     fn check_round_trip_u64(x: u64) {
         let mut buf = Vec::new();
@@ -56,6 +799,34 @@ mod tests {
         assert_eq!(x, y, "check_round_trip(0x{:x})", x);
     }
 
+    fn check_encode_u64(x: u64) {
+        let mut expected = Vec::new();
+        write_bvarint(x, &mut expected).unwrap();
+        let mut buf = [0u8; MAX_ENCODED_LEN];
+        let len = encode_bvarint(x, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &expected[..], "check_encode_u64(0x{:x})", x);
+    }
+
+    fn check_decode_u64(x: u64) {
+        let mut buf = Vec::new();
+        write_bvarint(x, &mut buf).unwrap();
+        let len = buf.len();
+        buf.push(0xff);
+        let decoded = decode_bvarint(&buf).unwrap();
+        assert_eq!(decoded, (x, len), "check_decode_u64(0x{:x})", x);
+    }
+
+    fn check_encoded_len_u64(x: u64) {
+        let mut buf = Vec::new();
+        write_bvarint(x, &mut buf).unwrap();
+        assert_eq!(
+            encoded_len(x),
+            buf.len(),
+            "check_encoded_len_u64(0x{:x})",
+            x
+        );
+    }
+
     fn check_order_u64(x: u64, y: u64) {
         let mut bufx = Vec::new();
         write_bvarint(x, &mut bufx).unwrap();
@@ -73,14 +844,132 @@ mod tests {
         );
     }
 
-    fn interesting_values() -> Vec<u64> {
+    fn check_prefix_free_u64(x: u64, y: u64) {
+        if x == y {
+            return;
+        }
+        let mut bufx = Vec::new();
+        write_bvarint(x, &mut bufx).unwrap();
+
+        let mut bufy = Vec::new();
+        write_bvarint(y, &mut bufy).unwrap();
+        assert!(
+            !bufx.starts_with(&bufy) && !bufy.starts_with(&bufx),
+            "check_prefix_free_u64(0x{:x}, 0x{:x}) {:?} {:?}",
+            x,
+            y,
+            bufx,
+            bufy,
+        );
+    }
+
+    fn check_cmp_encoded_u64(x: u64, y: u64) {
+        let mut bufx = Vec::new();
+        write_bvarint(x, &mut bufx).unwrap();
+
+        let mut bufy = Vec::new();
+        write_bvarint(y, &mut bufy).unwrap();
+        assert_eq!(
+            cmp_encoded(&bufx, &bufy).unwrap(),
+            x.cmp(&y),
+            "check_cmp_encoded_u64(0x{:x}, 0x{:x})",
+            x,
+            y,
+        );
+    }
+
+    fn check_len_monotonic_u64(x: u64, y: u64) {
+        let (x, y) = if x <= y { (x, y) } else { (y, x) };
+        assert!(
+            encoded_len(x) <= encoded_len(y),
+            "check_len_monotonic_u64(0x{:x}, 0x{:x})",
+            x,
+            y
+        );
+    }
+
+    /// Straightforward encoder to check [`write_bvarint`] against.
+    fn reference_encode(v: u64) -> Vec<u8> {
+        if v <= 0xf6 {
+            return vec![v as u8];
+        }
+        let mut bytes = Vec::new();
+        let mut rest = v;
+        while rest > 0 {
+            bytes.insert(0, (rest & 0xff) as u8);
+            rest >>= 8;
+        }
+        bytes.insert(0, 0xf6 + bytes.len() as u8);
+        bytes
+    }
+
+    fn check_reference_u64(x: u64) {
+        let mut buf = Vec::new();
+        write_bvarint(x, &mut buf).unwrap();
+        assert_eq!(buf, reference_encode(x), "check_reference_u64(0x{:x})", x);
+    }
+
+    pub(crate) fn interesting_values() -> Vec<u64> {
         vec![0, 0xef, 0x7ee, 0x8ee, 0x107ee, 0x108ee, u64::MAX - 3]
             .into_iter()
-            .chain((5..=63).map(|b| (1u64 << b) - 2))
+            // Under Miri, keep only the width boundaries, since many tests
+            // are quadratic in the number of values.
+            .chain(
+                (5..=63)
+                    .filter(|b| !cfg!(miri) || b % 8 == 0)
+                    .map(|b| (1u64 << b) - 2),
+            )
             .flat_map(|v| vec![v, v + 1, v + 2, v + 3])
             .collect()
     }
 
+    /// Largest value of each encoded width, indexed by width - 1.
+    fn width_maxes() -> Vec<u64> {
+        let mut maxes = vec![ONE_BYTE_MAX, TWO_BYTE_MAX, THREE_BYTE_MAX];
+        maxes.extend((3..8).map(|n| (1u64 << (8 * n)) - 1));
+        maxes.push(u64::MAX);
+        maxes
+    }
+
+    #[test]
+    fn test_order_width_boundaries() {
+        let maxes = width_maxes();
+        for (i, &max) in maxes.iter().enumerate() {
+            assert_eq!(encoded_len(max), i + 1);
+            if max < u64::MAX {
+                assert_eq!(encoded_len(max + 1), i + 2);
+            }
+        }
+
+        // Values near each boundary, tagged with their width.
+        let values: Vec<(usize, u64)> = maxes
+            .iter()
+            .flat_map(|&max| {
+                vec![
+                    max - 2,
+                    max - 1,
+                    max,
+                    max.wrapping_add(1),
+                    max.wrapping_add(2),
+                ]
+            })
+            .map(|v| (encoded_len(v), v))
+            .collect();
+        for &(wx, x) in &values {
+            for &(wy, y) in &values {
+                check_order_u64(x, y);
+                if wx < wy {
+                    // A shorter encoding always sorts before a longer one.
+                    let mut bufx = Vec::new();
+                    write_bvarint(x, &mut bufx).unwrap();
+                    let mut bufy = Vec::new();
+                    write_bvarint(y, &mut bufy).unwrap();
+                    assert!(bufx < bufy, "{:?} {:?}", bufx, bufy);
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_round_trip_u64_manual() {
         #[cfg(not(debug_assertions))]
@@ -92,6 +981,727 @@ mod tests {
         }
     }
 
+    /// Round-trip and order over every `u32`. Run before releases with
+    /// `cargo test --release -- --ignored exhaustive_u32`, which takes a
+    /// few minutes.
+    #[test]
+    #[ignore]
+    fn test_exhaustive_u32() {
+        let (mut prev, mut prev_len) = encode_to_stack(0);
+        for x in 1..=u32::MAX as u64 + 1 {
+            let (buf, len) = encode_to_stack(x);
+            assert_eq!(decode_bvarint(&buf[..len]).unwrap(), (x, len));
+            assert!(prev[..prev_len] < buf[..len], "order at 0x{:x}", x);
+            prev = buf;
+            prev_len = len;
+        }
+    }
+
+    #[test]
+    fn test_encode_u64_manual() {
+        for x in interesting_values() {
+            check_encode_u64(x);
+        }
+    }
+
+    #[test]
+    fn test_encode_buffer_too_small() {
+        let mut buf = [0xaa; 4];
+        assert_eq!(
+            encode_bvarint(0x1234_5678, &mut buf),
+            Err(EncodeError::BufferTooSmall { needed: 5 })
+        );
+        assert_eq!(buf, [0xaa; 4]);
+        assert_eq!(
+            encode_bvarint(0, &mut []),
+            Err(EncodeError::BufferTooSmall { needed: 1 })
+        );
+    }
+
+    #[test]
+    fn test_decode_u64_manual() {
+        for x in interesting_values() {
+            check_decode_u64(x);
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode_bvarint(&[]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(
+            decode_bvarint(&[0xf8, 1]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(
+            decode_bvarint(&[0xff, 1, 2]),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+    }
+
+    #[test]
+    fn test_smallest_inputs() {
+        let eof = |r: io::Result<u64>| r.unwrap_err().kind() == io::ErrorKind::UnexpectedEof;
+
+        // Empty input.
+        assert!(matches!(
+            decode_bvarint(&[]),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert!(eof(read_bvarint(&[][..])));
+        assert!(eof(read_bvarint_with_buf(&[][..], &mut [0; 8])));
+        assert!(read_bvarint_opt(&[][..]).unwrap().is_none());
+        assert!(decode_bvarint_iter(&mut core::iter::empty()).is_err());
+        assert!(read_bvarint_exact(&[]).is_err());
+        assert_eq!(count_values(&[]).unwrap(), 0);
+
+        // A single 0x00 is 0, using 1 byte.
+        assert_eq!(decode_bvarint(&[0]).unwrap(), (0, 1));
+        assert_eq!(read_bvarint(&[0][..]).unwrap(), 0);
+        assert_eq!(read_bvarint_exact(&[0]).unwrap(), 0);
+        assert_eq!(validate_bvarint(&[0]).unwrap(), 1);
+        let mut pos = 0;
+        assert_eq!(decode_bvarint_from_cursor(&[0], &mut pos).unwrap(), 0);
+        assert_eq!(pos, 1);
+
+        // A lone lead byte is truncated.
+        assert!(matches!(
+            decode_bvarint(&[0xf8]),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert!(eof(read_bvarint(&[0xf8][..])));
+        assert!(eof(read_bvarint_opt(&[0xf8][..]).map(|v| v.unwrap())));
+        assert!(matches!(
+            count_values(&[0xf8]),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert!(decode_bvarint_iter(&mut [0xf8].iter().copied()).is_err());
+    }
+
+    #[test]
+    fn test_encoded_len_manual() {
+        assert_eq!(encoded_len(0xf6), 1);
+        assert_eq!(encoded_len(0xf7), 2);
+        assert_eq!(encoded_len(0xff), 2);
+        assert_eq!(encoded_len(0x100), 3);
+        assert_eq!(encoded_len(u64::MAX), 9);
+        for x in interesting_values() {
+            check_encoded_len_u64(x);
+        }
+    }
+
+    #[test]
+    fn test_const_encoded_len() {
+        const LEN: usize = encoded_len(u64::MAX);
+        assert_eq!(LEN, MAX_ENCODED_LEN);
+        let _buf: [u8; encoded_len(0x1234)] = [0; 3];
+    }
+
+    #[test]
+    fn test_range_constants() {
+        assert_eq!(encoded_len(ONE_BYTE_MAX), 1);
+        assert_eq!(encoded_len(ONE_BYTE_MAX + 1), 2);
+        assert_eq!(encoded_len(TWO_BYTE_MAX), 2);
+        assert_eq!(encoded_len(TWO_BYTE_MAX + 1), 3);
+        assert_eq!(encoded_len(THREE_BYTE_MAX), 3);
+        assert_eq!(encoded_len(THREE_BYTE_MAX + 1), 4);
+    }
+
+    #[test]
+    fn test_write_width_boundaries() {
+        for &(x, lead, len) in &[
+            (0xf7, 0xf7, 2),
+            (0xffff, 0xf8, 3),
+            (0x10000, 0xf9, 4),
+            (0x107f0, 0xf9, 4),
+            (1 << 56, 0xfe, 9),
+            (u64::MAX, 0xfe, 9),
+        ] {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!((buf[0], buf.len()), (lead, len), "write_bvarint(0x{:x})", x);
+            assert_eq!(read_bvarint(&buf[..]).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_byte_width() {
+        assert_eq!(byte_width(0), 0);
+        for b in 0..64 {
+            let v = 1u64 << b;
+            for x in [v - 1, v, v + 1] {
+                let expected = 8 - x.to_be_bytes().iter().take_while(|&&b| b == 0).count();
+                assert_eq!(byte_width(x), expected, "byte_width(0x{:x})", x);
+                // The previous formula.
+                assert_eq!(
+                    byte_width(x),
+                    ((64 + 8 - 1 - x.leading_zeros()) / 8) as usize
+                );
+            }
+        }
+        assert_eq!(byte_width(u64::MAX), 8);
+    }
+
+    #[test]
+    fn test_value_range_for_width() {
+        assert_eq!(value_range_for_width(0), None);
+        assert_eq!(value_range_for_width(10), None);
+        assert_eq!(value_range_for_width(1), Some(0..=0xf6));
+        assert_eq!(value_range_for_width(2), Some(0xf7..=0xff));
+        assert_eq!(value_range_for_width(3), Some(0x100..=0xffff));
+        let mut next = 0;
+        for (i, &max) in width_maxes().iter().enumerate() {
+            let range = value_range_for_width(i + 1).unwrap();
+            assert_eq!(range, next..=max);
+            assert_eq!(encoded_len(*range.start()), i + 1);
+            assert_eq!(encoded_len(*range.end()), i + 1);
+            next = max.wrapping_add(1);
+        }
+    }
+
+    #[test]
+    fn test_one_byte_boundary() {
+        for &(x, expected) in &[
+            (0xf6, &[0xf6][..]),
+            (0xf7, &[0xf7, 0xf7]),
+            (0xff, &[0xf7, 0xff]),
+            (0x100, &[0xf8, 0x01, 0x00]),
+        ] {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(buf, expected, "write_bvarint(0x{:x})", x);
+            assert_eq!(read_bvarint(&buf[..]).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_peek_len() {
+        assert_eq!(peek_len(0), 1);
+        assert_eq!(peek_len(0xf6), 1);
+        assert_eq!(peek_len(0xf7), 2);
+        assert_eq!(peek_len(0xfe), 9);
+        assert_eq!(peek_len(0xff), 0);
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(peek_len(buf[0]), buf.len());
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        write_bvarint(42, &mut buf).unwrap();
+        let mut r = &buf[..];
+        for x in interesting_values() {
+            assert_eq!(skip_bvarint(&mut r).unwrap(), encoded_len(x));
+        }
+        assert_eq!(read_bvarint(&mut r).unwrap(), 42);
+
+        let err = skip_bvarint(&[0xf8, 1][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let err = skip_bvarint(&[0xff, 1, 2][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_error_from_io() {
+        let err = read_bvarint(&[0xff][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "exceeds u64::MAX (value at offset 0)");
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::ReservedLeadByte { .. }
+        ));
+
+        let err = read_bvarint(&[0xf8, 1][..]).unwrap_err();
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::UnexpectedEof { .. }
+        ));
+
+        let err = io::Error::other("x");
+        assert!(matches!(DecodeError::from(err), DecodeError::Io(_)));
+    }
+
+    #[test]
+    fn test_decode_from_cursor() {
+        let mut buf = Vec::new();
+        for &x in &[1, 0x1234, u64::MAX] {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        let mut pos = 0;
+        assert_eq!(decode_bvarint_from_cursor(&buf, &mut pos).unwrap(), 1);
+        assert_eq!(pos, 1);
+        assert_eq!(decode_bvarint_from_cursor(&buf, &mut pos).unwrap(), 0x1234);
+        assert_eq!(pos, 4);
+        assert_eq!(
+            decode_bvarint_from_cursor(&buf, &mut pos).unwrap(),
+            u64::MAX
+        );
+        assert_eq!(pos, buf.len());
+        assert!(matches!(
+            decode_bvarint_from_cursor(&buf, &mut pos),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert_eq!(pos, buf.len());
+
+        let mut pos = 1;
+        assert!(matches!(
+            decode_bvarint_from_cursor(&[1, 0xf8, 0], &mut pos),
+            Err(DecodeError::UnexpectedEof { offset: 1 })
+        ));
+        assert_eq!(pos, 1);
+    }
+
+    #[test]
+    fn test_decode_out() {
+        let mut out = 0;
+        for x in interesting_values() {
+            let (buf, len) = encode_to_stack(x);
+            let expected = decode_bvarint(&buf[..len]).unwrap();
+            assert_eq!(decode_bvarint_out(&buf[..len], &mut out).unwrap(), len);
+            assert_eq!((out, len), expected);
+        }
+        out = 7;
+        assert!(matches!(
+            decode_bvarint_out(&[0xf8, 0], &mut out),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert_eq!(out, 7);
+    }
+
+    #[test]
+    fn test_decode_unchecked() {
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            buf.push(0xff);
+            let expected = decode_bvarint(&buf).unwrap();
+            assert_eq!(unsafe { decode_bvarint_unchecked(&buf) }, expected);
+        }
+    }
+
+    #[test]
+    fn test_decode_const() {
+        const { assert!(decode_bvarint_const(&[0xf8, 0x12, 0x34]) == 0x1234) };
+        const { assert!(decode_bvarint_const(&[0xf6, 0xff]) == 0xf6) };
+        for x in interesting_values() {
+            let (buf, len) = encode_to_stack(x);
+            assert_eq!(decode_bvarint_const(&buf[..len]), x);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "truncated")]
+    fn test_decode_const_truncated() {
+        decode_bvarint_const(&[0xf8, 0x12]);
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved")]
+    fn test_decode_const_reserved() {
+        decode_bvarint_const(&[0xff; 9]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_decode_unchecked_truncated() {
+        unsafe { decode_bvarint_unchecked(&[0xf8, 0x12]) };
+    }
+
+    #[test]
+    fn test_validate() {
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(validate_bvarint(&buf).unwrap(), buf.len());
+        }
+        for buf in &[
+            &[0xf7, 0x01][..],
+            &[0xf8, 0x00, 0xff],
+            &[0xfe, 0, 0, 0, 0, 0, 0, 0, 1],
+        ] {
+            assert!(decode_bvarint(buf).is_ok());
+            assert!(matches!(
+                validate_bvarint(buf),
+                Err(DecodeError::NonCanonical)
+            ));
+        }
+        assert!(matches!(
+            validate_bvarint(&[0xf8, 0x01]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+        assert!(matches!(
+            validate_bvarint(&[0xff]),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+    }
+
+    /// Links the `no_panic` checked functions. Only meaningful in release
+    /// mode with `--cfg bvarint_no_panic`.
+    #[cfg(bvarint_no_panic)]
+    #[test]
+    fn test_no_panic() {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode_bvarint_no_panic(0x1234, &mut buf).unwrap();
+        assert_eq!(decode_bvarint(&buf[..len]).unwrap(), (0x1234, 3));
+    }
+
+    #[test]
+    fn test_read_exact() {
+        assert_eq!(read_bvarint_exact(&[0xf8, 0x12, 0x34]).unwrap(), 0x1234);
+        assert!(matches!(
+            read_bvarint_exact(&[0xf8, 0x12, 0x34, 0, 0]),
+            Err(DecodeError::TrailingBytes {
+                consumed: 3,
+                remaining: 2
+            })
+        ));
+        assert!(matches!(
+            read_bvarint_exact(&[0xf8, 0x12]),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+        assert!(matches!(
+            read_bvarint_exact(&[]),
+            Err(DecodeError::UnexpectedEof { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_read_limited() {
+        let mut buf = Vec::new();
+        write_bvarint(0x12345, &mut buf).unwrap();
+        assert_eq!(read_bvarint_limited(&buf[..], 4).unwrap(), 0x12345);
+        assert_eq!(read_bvarint_limited(&buf[..], 9).unwrap(), 0x12345);
+
+        let mut r = &buf[..];
+        let err = read_bvarint_limited(&mut r, 3).unwrap_err();
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::LimitExceeded {
+                needed: 4,
+                limit: 3
+            }
+        ));
+        // Only the lead byte was read.
+        assert_eq!(r.len(), 3);
+
+        assert!(read_bvarint_limited(&[0][..], 0).is_err());
+        assert_eq!(read_bvarint_limited(&[0][..], 1).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_budgeted() {
+        let mut buf = Vec::new();
+        for v in [1, 0x1234, 0x12345678, 2] {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        // 1 + 3 bytes fit, then the 5-byte value overruns the budget.
+        let mut budget = 6;
+        let mut r = &buf[..];
+        assert_eq!(read_bvarint_budgeted(&mut r, &mut budget).unwrap(), 1);
+        assert_eq!(read_bvarint_budgeted(&mut r, &mut budget).unwrap(), 0x1234);
+        assert_eq!(budget, 2);
+        let err = read_bvarint_budgeted(&mut r, &mut budget).unwrap_err();
+        assert!(matches!(
+            DecodeError::from(err),
+            DecodeError::LimitExceeded {
+                needed: 5,
+                limit: 2
+            }
+        ));
+        assert_eq!(budget, 2);
+        // Only the lead byte was read.
+        assert_eq!(r.len(), 5);
+
+        let mut budget = 0;
+        assert!(read_bvarint_budgeted(&[0][..], &mut budget).is_err());
+        let mut budget = u64::MAX;
+        assert_eq!(read_bvarint_budgeted(&buf[..], &mut budget).unwrap(), 1);
+        assert_eq!(budget, u64::MAX - 1);
+    }
+
+    /// Count `write` calls.
+    #[derive(Default)]
+    struct CountWrites(usize);
+
+    impl io::Write for CountWrites {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += 1;
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_single_call() {
+        for x in interesting_values() {
+            let mut w = CountWrites::default();
+            write_bvarint(x, &mut w).unwrap();
+            assert_eq!(w.0, 1, "write_bvarint(0x{:x})", x);
+        }
+    }
+
+    /// Record the slices of each call, taking at most `max` bytes per call.
+    struct RecordVectored {
+        calls: Vec<Vec<Vec<u8>>>,
+        max: usize,
+    }
+
+    impl io::Write for RecordVectored {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.write_vectored(&[io::IoSlice::new(buf)])
+        }
+        fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+            self.calls.push(bufs.iter().map(|b| b.to_vec()).collect());
+            Ok(bufs.iter().map(|b| b.len()).sum::<usize>().min(self.max))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn record_vectored(v: u64, max: usize) -> Vec<Vec<Vec<u8>>> {
+        let mut w = RecordVectored {
+            calls: Vec::new(),
+            max,
+        };
+        write_bvarint_vectored(v, &mut w).unwrap();
+        w.calls
+    }
+
+    #[test]
+    fn test_write_vectored() {
+        assert_eq!(record_vectored(5, 9), [[[5]]]);
+        assert_eq!(
+            record_vectored(0x12345, 9),
+            [[vec![0xf9], vec![0x01, 0x23, 0x45]]]
+        );
+        assert_eq!(
+            record_vectored(0x12345, 2),
+            [
+                vec![vec![0xf9], vec![0x01, 0x23, 0x45]],
+                vec![vec![0x23, 0x45]],
+            ]
+        );
+        let err = write_bvarint_vectored(0x12345, &mut [0u8; 0][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint_vectored(x, &mut buf).unwrap();
+            assert_eq!(buf, encode_to_stack(x).0[..encoded_len(x)]);
+        }
+    }
+
+    #[test]
+    fn test_write_partial() {
+        let x = 0x1234_5678;
+        let len = encoded_len(x);
+        for n in 0..len {
+            // A slice writer accepts `n` bytes, then fails with `WriteZero`.
+            let mut buf = [0u8; MAX_ENCODED_LEN];
+            let mut w = &mut buf[..n];
+            let err = write_bvarint(x, &mut w).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+            assert_eq!(buf[..n], encode_to_stack(x).0[..n]);
+            let err = read_bvarint(&buf[..n]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+    }
+
+    #[test]
+    fn test_write_counted() {
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            assert_eq!(write_bvarint_counted(x, &mut buf).unwrap(), encoded_len(x));
+            assert_eq!(buf.len(), encoded_len(x));
+            assert_eq!(read_bvarint(&buf[..]).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_encode_to_stack() {
+        for x in interesting_values() {
+            let mut expected = Vec::new();
+            write_bvarint(x, &mut expected).unwrap();
+            let (buf, len) = encode_to_stack(x);
+            assert_eq!(&buf[..len], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn test_bvarint_bytes() {
+        let mut expected = Vec::new();
+        for x in interesting_values() {
+            let start = expected.len();
+            write_bvarint(x, &mut expected).unwrap();
+            assert_eq!(bvarint_bytes(x).len(), expected.len() - start);
+            assert_eq!(bvarint_bytes(x).collect::<Vec<u8>>(), expected[start..]);
+        }
+        let chained: Vec<u8> = interesting_values()
+            .into_iter()
+            .flat_map(bvarint_bytes)
+            .collect();
+        assert_eq!(chained, expected);
+    }
+
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut h = DefaultHasher::new();
+        let mut expected = DefaultHasher::new();
+        for x in interesting_values() {
+            hash_bvarint(x, &mut h);
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            expected.write(&buf);
+        }
+        assert_eq!(h.finish(), expected.finish());
+    }
+
+    #[test]
+    fn test_write_into() {
+        let mut storage = [0u8; 6];
+        let mut buf = &mut storage[..];
+        write_bvarint_into(1, &mut buf).unwrap();
+        write_bvarint_into(0x1234, &mut buf).unwrap();
+        assert_eq!(buf.len(), 2);
+        assert_eq!(
+            write_bvarint_into(0x123456, &mut buf),
+            Err(EncodeError::BufferTooSmall { needed: 4 })
+        );
+        assert_eq!(buf.len(), 2);
+        write_bvarint_into(0xf7, &mut buf).unwrap();
+        assert!(buf.is_empty());
+        assert_eq!(storage, [1, 0xf8, 0x12, 0x34, 0xf7, 0xf7]);
+    }
+
+    #[test]
+    fn test_read_with_buf() {
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        let mut r = &buf[..];
+        let mut scratch = [0xaa; 8];
+        for x in interesting_values() {
+            assert_eq!(read_bvarint_with_buf(&mut r, &mut scratch).unwrap(), x);
+        }
+        assert!(read_bvarint_with_buf(&[0xff][..], &mut scratch).is_err());
+        assert!(read_bvarint_with_buf(&[0xf8, 1][..], &mut scratch).is_err());
+    }
+
+    #[test]
+    fn test_read_with_bytes() {
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        let mut r = &buf[..];
+        for x in interesting_values() {
+            let (v, bytes, len) = read_bvarint_with_bytes(&mut r).unwrap();
+            assert_eq!(v, x);
+            assert_eq!(read_bvarint_exact(&bytes[..len]).unwrap(), x);
+        }
+        let (v, bytes, len) = read_bvarint_with_bytes(&[0xf8, 0, 1, 2][..]).unwrap();
+        assert_eq!((v, &bytes[..len]), (1, &[0xf8, 0, 1][..]));
+        assert!(read_bvarint_with_bytes(&[0xff][..]).is_err());
+        let err = read_bvarint_with_bytes(&[0xf8, 1][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_lenient() {
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(read_bvarint_lenient(&buf[..]).unwrap(), (x, false));
+        }
+        for (buf, x) in &[
+            (&[0xf7, 0x01][..], 1),
+            (&[0xf8, 0x00, 0xff], 0xff),
+            (&[0xfe, 0, 0, 0, 0, 0, 0, 0x12, 0x34], 0x1234),
+        ] {
+            assert_eq!(read_bvarint_lenient(&buf[..]).unwrap(), (*x, true));
+        }
+        assert!(read_bvarint_lenient(&[0xff][..]).is_err());
+        assert!(read_bvarint_lenient(&[0xf8, 1][..]).is_err());
+    }
+
+    #[test]
+    fn test_read_canonical() {
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        let mut r = &buf[..];
+        for x in interesting_values() {
+            assert_eq!(read_bvarint_canonical(&mut r).unwrap(), x);
+        }
+        for buf in &[&[0xf7, 0x01][..], &[0xf9, 0x00, 0x12, 0x34]] {
+            let err = read_bvarint_canonical(&buf[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(matches!(DecodeError::from(err), DecodeError::NonCanonical));
+        }
+    }
+
+    #[test]
+    fn test_read_bounded() {
+        assert_eq!(read_bvarint_bounded(&[3][..], 3).unwrap(), 3);
+        let err = read_bvarint_bounded(&[4][..], 3).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "value 4 exceeds max 3");
+    }
+
+    #[test]
+    fn test_read_nonzero() {
+        let one = read_bvarint_nonzero(&[1][..]).unwrap();
+        assert_eq!(Some(one), core::num::NonZeroU64::new(1));
+        let max = read_bvarint_nonzero(&[0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff][..]);
+        assert_eq!(max.unwrap().get(), u64::MAX);
+
+        let err = read_bvarint_nonzero(&[0][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "value is zero");
+        let err = read_bvarint_nonzero(&[0xf7, 0x00][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_decode_iter() {
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        let mut it = buf.iter().copied();
+        for x in interesting_values() {
+            assert_eq!(decode_bvarint_iter(&mut it).unwrap(), x);
+        }
+        assert!(matches!(
+            decode_bvarint_iter(&mut it),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+
+        let mut it = vec![0xf8, 0x12].into_iter().chain(Some(0x34));
+        assert_eq!(decode_bvarint_iter(&mut it).unwrap(), 0x1234);
+        let mut it = vec![0xf8, 0x12].into_iter();
+        assert!(matches!(
+            decode_bvarint_iter(&mut it),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+
     #[test]
     fn test_order_manual() {
         #[cfg(not(debug_assertions))]
@@ -106,11 +1716,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_count_values() {
+        let mut buf = Vec::new();
+        assert_eq!(count_values(&buf).unwrap(), 0);
+        for x in interesting_values() {
+            write_bvarint(x, &mut buf).unwrap();
+        }
+        assert_eq!(count_values(&buf).unwrap(), interesting_values().len());
+
+        buf.push(0xf8);
+        let start = buf.len() - 1;
+        assert!(matches!(
+            count_values(&buf),
+            Err(DecodeError::UnexpectedEof { offset }) if offset == start
+        ));
+        assert!(matches!(
+            count_values(&[1, 0xff, 2]),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cmp_encoded() {
+        let values = interesting_values();
+        for x in &values {
+            for y in &values {
+                check_cmp_encoded_u64(*x, *y);
+            }
+        }
+        assert!(matches!(
+            cmp_encoded(&[0xf7, 0x01], &[0x02]),
+            Err(DecodeError::NonCanonical)
+        ));
+        assert!(matches!(
+            cmp_encoded(&[0x01], &[0x02, 0x03]),
+            Err(DecodeError::TrailingBytes { .. })
+        ));
+        assert!(matches!(
+            cmp_encoded(&[0x01], &[0xf8]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cmp_encoded_quickcheck() {
+        quickcheck(check_cmp_encoded_u64 as fn(u64, u64));
+    }
+
+    #[test]
+    fn test_len_monotonic_manual() {
+        // Checking neighbours is enough for a range.
+        for x in 0..0x20000 {
+            check_len_monotonic_u64(x, x + 1);
+        }
+        for max in width_maxes() {
+            check_len_monotonic_u64(max, max.saturating_add(1));
+        }
+        let values = interesting_values();
+        for x in &values {
+            for y in &values {
+                check_len_monotonic_u64(*x, *y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_len_monotonic_quickcheck() {
+        quickcheck(check_len_monotonic_u64 as fn(u64, u64));
+    }
+
+    #[test]
+    fn test_prefix_free_manual() {
+        let values = interesting_values();
+        for x in &values {
+            for y in &values {
+                check_prefix_free_u64(*x, *y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix_free_quickcheck() {
+        quickcheck(check_prefix_free_u64 as fn(u64, u64));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "too slow under Miri")]
+    fn test_reference_exhaustive_low() {
+        for x in 0..0x20000 {
+            check_reference_u64(x);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "too slow under Miri")]
+    fn test_reference_random() {
+        let mut x = 0x2545f4914f6cdd1du64;
+        for i in 0..100000 {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            // Spread over all widths.
+            check_reference_u64(x >> (i % 64));
+        }
+        for x in interesting_values() {
+            check_reference_u64(x);
+        }
+    }
+
     #[test]
     fn test_round_trip_u64_quickcheck() {
         quickcheck(check_round_trip_u64 as fn(u64));
     }
 
+    #[test]
+    fn test_encode_u64_quickcheck() {
+        quickcheck(check_encode_u64 as fn(u64));
+    }
+
+    #[test]
+    fn test_decode_u64_quickcheck() {
+        quickcheck(check_decode_u64 as fn(u64));
+    }
+
+    #[test]
+    fn test_encoded_len_u64_quickcheck() {
+        quickcheck(check_encoded_len_u64 as fn(u64));
+    }
+
     #[test]
     fn test_order_u64_quickcheck() {
         quickcheck(check_order_u64 as fn(u64, u64));
@@ -0,0 +1,70 @@
+//! Writing at file offsets, without seeking.
+
+use crate::encode_to_stack;
+use std::io;
+
+#[cfg(unix)]
+use std::os::unix::fs::FileExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileExt;
+
+/// Encode `v` and write it at `offset` of `file`. Return the number of
+/// bytes written.
+///
+/// This does not use or move the file cursor on unix, so concurrent writers
+/// of one file do not race on it. On windows, the cursor is moved.
+pub fn write_bvarint_at(v: u64, file: &impl FileExt, offset: u64) -> io::Result<usize> {
+    let (buf, len) = encode_to_stack(v);
+    write_all_at(file, &buf[..len], offset)?;
+    Ok(len)
+}
+
+#[cfg(unix)]
+fn write_all_at(file: &impl FileExt, buf: &[u8], offset: u64) -> io::Result<()> {
+    file.write_all_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn write_all_at(file: &impl FileExt, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match file.seek_write(buf, offset) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode_bvarint;
+    use std::fs;
+
+    #[test]
+    #[cfg_attr(miri, ignore = "Miri isolation rejects file access")]
+    fn test_write_at() {
+        let path = std::env::temp_dir().join(format!("bvarint-write-at-{}", std::process::id()));
+        let file = fs::File::create(&path).unwrap();
+        let entries = [(20, u64::MAX), (0, 1), (5, 0x1234), (40, 0xf7)];
+        for &(offset, v) in &entries {
+            assert_eq!(
+                write_bvarint_at(v, &file, offset).unwrap(),
+                crate::encoded_len(v)
+            );
+        }
+        drop(file);
+        let data = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(data.len(), 42);
+        for &(offset, v) in &entries {
+            let (decoded, _) = decode_bvarint(&data[offset as usize..]).unwrap();
+            assert_eq!(decoded, v);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! `serde` support for [`Bvarint`].
+//!
+//! Human-readable formats get the plain `u64`. Other formats get the encoded
+//! bytes as a tuple of `u8`. A tuple is used instead of `serialize_bytes`
+//! because formats like bincode prefix bytes with a fixed-width length,
+//! which would make the value larger than a plain `u64`. The deserializer
+//! asks for a tuple of `MAX_ENCODED_LEN` bytes but only reads as many as the
+//! lead byte implies.
+
+use crate::decode_bvarint;
+use crate::encode_bvarint;
+use crate::peek_len;
+use crate::Bvarint;
+use crate::MAX_ENCODED_LEN;
+use core::fmt;
+use serde::de;
+use serde::ser::SerializeTuple;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+impl Serialize for Bvarint {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            return serializer.serialize_u64(self.0);
+        }
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode_bvarint(self.0, &mut buf).expect("9 bytes fit any u64");
+        let mut tuple = serializer.serialize_tuple(len)?;
+        for b in &buf[..len] {
+            tuple.serialize_element(b)?;
+        }
+        tuple.end()
+    }
+}
+
+struct BvarintVisitor;
+
+impl<'de> de::Visitor<'de> for BvarintVisitor {
+    type Value = Bvarint;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bvarint encoded bytes")
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Bvarint, A::Error> {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        buf[0] = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let len = peek_len(buf[0]);
+        for (i, b) in buf.iter_mut().enumerate().take(len).skip(1) {
+            *b = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        match decode_bvarint(&buf) {
+            Ok((v, _)) => Ok(Bvarint(v)),
+            Err(e) => Err(de::Error::custom(e)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Bvarint {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Bvarint, D::Error> {
+        if deserializer.is_human_readable() {
+            return u64::deserialize(deserializer).map(Bvarint);
+        }
+        deserializer.deserialize_tuple(MAX_ENCODED_LEN, BvarintVisitor)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::encoded_len;
+    use crate::tests::interesting_values;
+
+    #[test]
+    fn test_bincode_round_trip() {
+        let values: Vec<Bvarint> = interesting_values().into_iter().map(Bvarint).collect();
+        let buf = bincode::serialize(&values).unwrap();
+        let total: usize = interesting_values().into_iter().map(encoded_len).sum();
+        // bincode prefixes the Vec with an 8-byte length.
+        assert_eq!(buf.len(), 8 + total);
+        let decoded: Vec<Bvarint> = bincode::deserialize(&buf).unwrap();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn test_bincode_reserved() {
+        let result: Result<Bvarint, _> = bincode::deserialize(&[0xff, 0, 0]);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Signed integers.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use std::io;
+
+/// Map `v` to `u64` so small magnitudes become small values: `0, -1, 1, -2`
+/// map to `0, 1, 2, 3`.
+///
+/// This is the same mapping as protobuf `sint64`, so a value written by
+/// [`write_bvarint_i64`] and a value written by a protobuf zigzag encoder
+/// decode to the same `i64`, although the bytes differ.
+pub const fn zigzag_encode(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+/// Inverse of [`zigzag_encode`].
+pub const fn zigzag_decode(v: u64) -> i64 {
+    ((v >> 1) as i64) ^ -((v & 1) as i64)
+}
+
+/// Encode `v` using zigzag mapping and write it to `w`.
+///
+/// Small-magnitude values, positive or negative, take fewer bytes.
+/// The encoding is NOT order-preserving: zigzag interleaves signs, so
+/// `-1` sorts after `0` and before `1`.
+pub fn write_bvarint_i64(v: i64, w: impl io::Write) -> io::Result<()> {
+    write_bvarint(zigzag_encode(v), w)
+}
+
+/// Read a value written by [`write_bvarint_i64`] from `r`.
+pub fn read_bvarint_i64(r: impl io::Read) -> io::Result<i64> {
+    read_bvarint(r).map(zigzag_decode)
+}
+
+/// Encode `v` so the encoded bytes sort in the same order as `v`, and write
+/// it to `w`.
+///
+/// `v` is mapped to `u64` by flipping the sign bit, so `i64::MIN` becomes 0
+/// and `i64::MAX` becomes `u64::MAX`. Unlike [`write_bvarint_i64`], small
+/// negative values take the full 9 bytes.
+pub fn write_bvarint_i64_ordered(v: i64, w: impl io::Write) -> io::Result<()> {
+    write_bvarint((v as u64) ^ (1 << 63), w)
+}
+
+/// Read a value written by [`write_bvarint_i64_ordered`] from `r`.
+pub fn read_bvarint_i64_ordered(r: impl io::Read) -> io::Result<i64> {
+    read_bvarint(r).map(|v| (v ^ (1 << 63)) as i64)
+}
+
+/// Encode `v` as its signed distance from `base`, using zigzag mapping,
+/// and write it to `w`.
+///
+/// Values just below or above `base` take few bytes. The distance wraps
+/// around, so every `v` round-trips, and values near `u64::MAX` are close
+/// to a `base` near 0. Like [`write_bvarint_i64`], the encoding is NOT
+/// order-preserving.
+pub fn write_bvarint_rebased(v: u64, base: u64, w: impl io::Write) -> io::Result<()> {
+    write_bvarint_i64(v.wrapping_sub(base) as i64, w)
+}
+
+/// Read a value written by [`write_bvarint_rebased`] with the same `base`
+/// from `r`.
+pub fn read_bvarint_rebased(r: impl io::Read, base: u64) -> io::Result<u64> {
+    read_bvarint_i64(r).map(|d| base.wrapping_add(d as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn check_round_trip_i64(x: i64) {
+        let mut buf = Vec::new();
+        write_bvarint_i64(x, &mut buf).unwrap();
+        let y = read_bvarint_i64(&buf[..]).unwrap();
+        assert_eq!(x, y, "check_round_trip_i64({})", x);
+    }
+
+    fn check_round_trip_i64_ordered(x: i64) {
+        let mut buf = Vec::new();
+        write_bvarint_i64_ordered(x, &mut buf).unwrap();
+        let y = read_bvarint_i64_ordered(&buf[..]).unwrap();
+        assert_eq!(x, y, "check_round_trip_i64_ordered({})", x);
+    }
+
+    fn check_order_i64(x: i64, y: i64) {
+        let mut bufx = Vec::new();
+        write_bvarint_i64_ordered(x, &mut bufx).unwrap();
+
+        let mut bufy = Vec::new();
+        write_bvarint_i64_ordered(y, &mut bufy).unwrap();
+        assert_eq!(
+            x.cmp(&y),
+            bufx.cmp(&bufy),
+            "check_order_i64({}, {}) {:?} {:?}",
+            x,
+            y,
+            bufx,
+            bufy,
+        );
+    }
+
+    fn interesting_values_i64() -> Vec<i64> {
+        interesting_values()
+            .into_iter()
+            .flat_map(|v| vec![v as i64, (v as i64).wrapping_neg()])
+            .chain(vec![i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX])
+            .collect()
+    }
+
+    #[test]
+    fn test_zigzag() {
+        // From the protobuf encoding guide, extended to 64 bits.
+        let table: &[(i64, u64)] = &[
+            (0, 0),
+            (-1, 1),
+            (1, 2),
+            (-2, 3),
+            (0x7fff_ffff, 0xffff_fffe),
+            (-0x8000_0000, 0xffff_ffff),
+            (i64::MAX, u64::MAX - 1),
+            (i64::MIN, u64::MAX),
+        ];
+        for &(signed, unsigned) in table {
+            assert_eq!(zigzag_encode(signed), unsigned);
+            assert_eq!(zigzag_decode(unsigned), signed);
+        }
+    }
+
+    #[test]
+    fn test_small_negative_single_byte() {
+        for x in -2..=2 {
+            let mut buf = Vec::new();
+            write_bvarint_i64(x, &mut buf).unwrap();
+            assert_eq!(buf.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_i64_manual() {
+        for x in interesting_values_i64() {
+            check_round_trip_i64(x);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_i64_quickcheck() {
+        quickcheck(check_round_trip_i64 as fn(i64));
+    }
+
+    #[test]
+    fn test_round_trip_i64_ordered_manual() {
+        for x in interesting_values_i64() {
+            check_round_trip_i64_ordered(x);
+        }
+    }
+
+    #[test]
+    fn test_order_i64_manual() {
+        let values = interesting_values_i64();
+        for x in &values {
+            for y in &values {
+                check_order_i64(*x, *y);
+            }
+        }
+    }
+
+    fn check_round_trip_rebased(x: u64, base: u64) {
+        let mut buf = Vec::new();
+        write_bvarint_rebased(x, base, &mut buf).unwrap();
+        let y = read_bvarint_rebased(&buf[..], base).unwrap();
+        assert_eq!(x, y, "check_round_trip_rebased({}, {})", x, base);
+    }
+
+    #[test]
+    fn test_rebased_near_base() {
+        // Milliseconds since the Unix epoch, around 2020.
+        let base = 1_600_000_000_000u64;
+        for delta in -100i64..=100 {
+            let x = base.wrapping_add(delta as u64);
+            let mut buf = Vec::new();
+            write_bvarint_rebased(x, base, &mut buf).unwrap();
+            assert_eq!(buf.len(), 1, "delta {}", delta);
+            assert_eq!(read_bvarint_rebased(&buf[..], base).unwrap(), x);
+        }
+        for x in interesting_values() {
+            for &base in &[0, 1, base, u64::MAX] {
+                check_round_trip_rebased(x, base);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_rebased_quickcheck() {
+        quickcheck(check_round_trip_rebased as fn(u64, u64));
+    }
+
+    #[test]
+    fn test_round_trip_i64_ordered_quickcheck() {
+        quickcheck(check_round_trip_i64_ordered as fn(i64));
+    }
+
+    #[test]
+    fn test_order_i64_quickcheck() {
+        quickcheck(check_order_i64 as fn(i64, i64));
+    }
+}
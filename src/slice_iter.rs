@@ -0,0 +1,172 @@
+//! Iterating over values packed in a slice.
+
+use crate::decode_bvarint_from_cursor;
+use crate::DecodeError;
+use core::ops::ControlFlow;
+
+/// Iterate over integers decoded from a byte slice, without allocating.
+///
+/// Iteration ends at the end of the slice. A truncated or invalid value is
+/// yielded as an error, with the offset where it starts, and ends the
+/// iteration.
+///
+/// The slice is only borrowed shared, so read-only regions such as
+/// memory-mapped files work without copying. [`remaining`](Self::remaining)
+/// borrows from the slice, not from the iterator, so it can outlive it.
+#[derive(Debug, Clone)]
+pub struct BvarintSliceIter<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BvarintSliceIter<'a> {
+    /// Iterate over the values in `buf`, starting at its front.
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    /// The offset of the next value in the slice.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The bytes not yet decoded.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.buf[self.pos..]
+    }
+}
+
+impl<'a> Iterator for BvarintSliceIter<'a> {
+    type Item = Result<u64, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos == self.buf.len() {
+            return None;
+        }
+        let result = decode_bvarint_from_cursor(self.buf, &mut self.pos);
+        if result.is_err() {
+            self.pos = self.buf.len();
+        }
+        Some(result)
+    }
+}
+
+impl core::iter::FusedIterator for BvarintSliceIter<'_> {}
+
+/// Fold `f` over the integers decoded from `buf`, stopping early when `f`
+/// returns `ControlFlow::Break`.
+///
+/// Returns the accumulator from the `Break`, or from the last `Continue`
+/// at the end of `buf`. Bytes after a `Break` are not decoded, so they are
+/// not validated either.
+pub fn fold_bvarints<B>(
+    buf: &[u8],
+    init: B,
+    mut f: impl FnMut(B, u64) -> ControlFlow<B, B>,
+) -> Result<B, DecodeError> {
+    let mut acc = init;
+    let mut pos = 0;
+    while pos < buf.len() {
+        let v = decode_bvarint_from_cursor(buf, &mut pos)?;
+        match f(acc, v) {
+            ControlFlow::Continue(next) => acc = next,
+            ControlFlow::Break(done) => return Ok(done),
+        }
+    }
+    Ok(acc)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::encode_all_to_vec;
+    use crate::tests::interesting_values;
+
+    #[test]
+    fn test_slice_iter() {
+        let values = interesting_values();
+        let buf = encode_all_to_vec(&values);
+        let decoded: Result<Vec<u64>, _> = BvarintSliceIter::new(&buf).collect();
+        assert_eq!(decoded.unwrap(), values);
+        assert!(BvarintSliceIter::new(&[]).next().is_none());
+    }
+
+    #[test]
+    fn test_fold() {
+        let buf = encode_all_to_vec(&[1, 0x1234, 3]);
+        let sum = fold_bvarints(&buf, 0, |sum, v| ControlFlow::Continue(sum + v));
+        assert_eq!(sum.unwrap(), 0x1238);
+        assert_eq!(
+            fold_bvarints(&[], 7, |_, _| ControlFlow::Break(0)).unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_fold_break() {
+        // Find the first value over 0x100, then stop before the invalid tail.
+        let mut buf = encode_all_to_vec(&[1, 0x1234, 3, 0x5678]);
+        buf.push(0xff);
+        let mut seen = 0;
+        let found = fold_bvarints(&buf, None, |_, v| {
+            seen += 1;
+            if v > 0x100 {
+                ControlFlow::Break(Some(v))
+            } else {
+                ControlFlow::Continue(None)
+            }
+        });
+        assert_eq!(found.unwrap(), Some(0x1234));
+        assert_eq!(seen, 2);
+
+        let err = fold_bvarints(&buf, (), |_, _| ControlFlow::Continue(()));
+        assert!(matches!(
+            err,
+            Err(DecodeError::ReservedLeadByte { offset: 8 })
+        ));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore = "leaks the region on purpose")]
+    fn test_slice_iter_borrow() {
+        // A leaked, read-only region stands in for a memory-mapped file.
+        let region: &'static [u8] =
+            Box::leak(encode_all_to_vec(&[1, 0x1234, 3]).into_boxed_slice());
+        let rest: &'static [u8] = {
+            let mut it = BvarintSliceIter::new(region);
+            assert_eq!(it.next().unwrap().unwrap(), 1);
+            it.remaining()
+        };
+        let decoded: Result<Vec<u64>, _> = BvarintSliceIter::new(rest).collect();
+        assert_eq!(decoded.unwrap(), [0x1234, 3]);
+        assert_eq!(crate::decode_bvarint(region).unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn test_slice_iter_truncated() {
+        let mut buf = encode_all_to_vec(&[1, 0x1234]);
+        buf.extend_from_slice(&[0xf9, 1, 2]);
+        let mut it = BvarintSliceIter::new(&buf);
+        assert_eq!(it.next().unwrap().unwrap(), 1);
+        assert_eq!(it.next().unwrap().unwrap(), 0x1234);
+        assert_eq!(it.position(), 4);
+        assert_eq!(it.remaining(), [0xf9, 1, 2]);
+        assert!(matches!(
+            it.next(),
+            Some(Err(DecodeError::UnexpectedEof { offset: 4 }))
+        ));
+        assert!(it.next().is_none());
+        assert!(it.remaining().is_empty());
+    }
+
+    #[test]
+    fn test_slice_iter_overflow() {
+        let mut it = BvarintSliceIter::new(&[1, 0xff, 2]);
+        assert_eq!(it.next().unwrap().unwrap(), 1);
+        assert!(matches!(
+            it.next(),
+            Some(Err(DecodeError::ReservedLeadByte { offset: 1 }))
+        ));
+        assert!(it.next().is_none());
+    }
+}
@@ -0,0 +1,163 @@
+//! Booleans, options, tags and small enums in the single-byte range.
+
+use crate::read_bvarint;
+use crate::write_bvarint;
+use std::io;
+
+/// Encode `v` as `0` or `1` and write it to `w`.
+pub fn write_bvarint_bool(v: bool, w: impl io::Write) -> io::Result<()> {
+    write_bvarint(v as u64, w)
+}
+
+/// Read a value written by [`write_bvarint_bool`] from `r`.
+///
+/// Values other than `0` and `1` are rejected.
+pub fn read_bvarint_bool(r: impl io::Read) -> io::Result<bool> {
+    match read_bvarint(r)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        v => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid bool {}", v),
+        )),
+    }
+}
+
+/// Encode `v` as `0` for `None` or `x + 1` for `Some(x)` and write it to
+/// `w`.
+///
+/// Because of the shift, `Some(u64::MAX)` cannot be encoded and is reported
+/// as `InvalidInput`.
+pub fn write_bvarint_option(v: Option<u64>, w: impl io::Write) -> io::Result<()> {
+    let v = match v {
+        None => 0,
+        Some(x) => x.checked_add(1).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Some(u64::MAX) cannot be encoded",
+            )
+        })?,
+    };
+    write_bvarint(v, w)
+}
+
+/// Read a value written by [`write_bvarint_option`] from `r`.
+pub fn read_bvarint_option(r: impl io::Read) -> io::Result<Option<u64>> {
+    Ok(read_bvarint(r)?.checked_sub(1))
+}
+
+/// Encode `v` with a 2-bit `tag` packed into the low bits, as
+/// `(v << 2) | tag`, and write it to `w`.
+///
+/// `tag` must be in `0..=3` and `v` below `2^62`, otherwise `InvalidInput`
+/// is returned.
+pub fn write_bvarint_tagged(tag: u8, v: u64, w: impl io::Write) -> io::Result<()> {
+    if tag > 3 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("tag {} exceeds 3", tag),
+        ));
+    }
+    if v >> 62 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("value {} exceeds 2^62 - 1", v),
+        ));
+    }
+    write_bvarint((v << 2) | tag as u64, w)
+}
+
+/// Read a value written by [`write_bvarint_tagged`] from `r` and return
+/// `(tag, v)`.
+pub fn read_bvarint_tagged(r: impl io::Read) -> io::Result<(u8, u64)> {
+    let v = read_bvarint(r)?;
+    Ok(((v & 3) as u8, v >> 2))
+}
+
+/// Read an integer from `r` and convert it using `f`.
+///
+/// `f` returns `None` for values that are not valid variants, which are
+/// reported as `InvalidData`. This is intended for `#[repr(u8)]`-style
+/// enums:
+///
+/// ```
+/// # use bvarint::read_bvarint_enum;
+/// #[derive(Debug, PartialEq)]
+/// enum Color { Red = 0, Green = 1 }
+///
+/// let color = read_bvarint_enum(&[1][..], |v| match v {
+///     0 => Some(Color::Red),
+///     1 => Some(Color::Green),
+///     _ => None,
+/// });
+/// assert_eq!(color.unwrap(), Color::Green);
+/// ```
+pub fn read_bvarint_enum<T>(r: impl io::Read, f: impl FnOnce(u64) -> Option<T>) -> io::Result<T> {
+    let v = read_bvarint(r)?;
+    f(v).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid enum value {}", v),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bool() {
+        for &v in &[false, true] {
+            let mut buf = Vec::new();
+            write_bvarint_bool(v, &mut buf).unwrap();
+            assert_eq!(buf, [v as u8]);
+            assert_eq!(read_bvarint_bool(&buf[..]).unwrap(), v);
+        }
+        let err = read_bvarint_bool(&[2][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_option() {
+        for &v in &[None, Some(0), Some(0xf6), Some(u64::MAX - 1)] {
+            let mut buf = Vec::new();
+            write_bvarint_option(v, &mut buf).unwrap();
+            assert_eq!(read_bvarint_option(&buf[..]).unwrap(), v);
+        }
+        let mut buf = Vec::new();
+        write_bvarint_option(None, &mut buf).unwrap();
+        assert_eq!(buf, [0]);
+        let err = write_bvarint_option(Some(u64::MAX), &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(buf, [0]);
+    }
+
+    #[test]
+    fn test_tagged() {
+        for tag in 0..=3 {
+            for &v in &[0, 1, 0x3d, 0x3e, (1 << 62) - 1] {
+                let mut buf = Vec::new();
+                write_bvarint_tagged(tag, v, &mut buf).unwrap();
+                assert_eq!(read_bvarint_tagged(&buf[..]).unwrap(), (tag, v));
+            }
+        }
+        // Small values share the single byte with the tag.
+        let mut buf = Vec::new();
+        write_bvarint_tagged(3, 0x3c, &mut buf).unwrap();
+        assert_eq!(buf, [0xf3]);
+
+        let err = write_bvarint_tagged(4, 0, &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = write_bvarint_tagged(0, 1 << 62, &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_enum() {
+        let f = |v| if v < 3 { Some(v as u8) } else { None };
+        assert_eq!(read_bvarint_enum(&[2][..], f).unwrap(), 2);
+        let err = read_bvarint_enum(&[3][..], f).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,40 @@
+//! `smallvec` support for [`BvarintVecExt`], to stage encodings without
+//! heap allocation.
+
+use crate::encode_to_stack;
+use crate::BvarintVecExt;
+use smallvec::Array;
+use smallvec::SmallVec;
+
+impl<A: Array<Item = u8>> BvarintVecExt for SmallVec<A> {
+    fn push_bvarint(&mut self, v: u64) {
+        let (buf, len) = encode_to_stack(v);
+        self.extend_from_slice(&buf[..len]);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::write_bvarint;
+
+    #[test]
+    fn test_push_smallvec() {
+        let mut expected = Vec::new();
+        let mut buf = SmallVec::<[u8; 16]>::new();
+        for &x in &[1, 0x1234, 0xf7, 0x123456] {
+            write_bvarint(x, &mut expected).unwrap();
+            buf.push_bvarint(x);
+        }
+        assert_eq!(&buf[..], &expected[..]);
+        assert!(!buf.spilled());
+
+        for x in interesting_values() {
+            write_bvarint(x, &mut expected).unwrap();
+            buf.push_bvarint(x);
+        }
+        assert_eq!(&buf[..], &expected[..]);
+        assert!(buf.spilled());
+    }
+}
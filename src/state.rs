@@ -0,0 +1,208 @@
+//! Incremental decoding, independent of any reader.
+
+#[cfg(feature = "alloc")]
+use crate::decode_bvarint;
+#[cfg(feature = "alloc")]
+use crate::peek_len;
+use crate::DecodeError;
+#[cfg(feature = "alloc")]
+use crate::MAX_ENCODED_LEN;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Decode a value from bytes pushed one at a time.
+///
+/// This suits poll-based or event-driven I/O where bytes arrive
+/// individually and blocking on a reader is not an option.
+#[derive(Debug, Clone, Default)]
+pub struct BvarintDecodeState {
+    value: u64,
+    remaining: u8,
+}
+
+impl BvarintDecodeState {
+    /// Create a decoder waiting for the first byte of a value.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Push the next byte.
+    ///
+    /// Return `Some(value)` once a complete encoding has arrived, and then
+    /// start over for the next value. Return `None` if more bytes are
+    /// needed.
+    ///
+    /// A `0xff` lead byte is an error. The state is reset, so the next
+    /// byte is treated as a lead byte.
+    pub fn push_byte(&mut self, b: u8) -> Result<Option<u64>, DecodeError> {
+        if self.remaining > 0 {
+            self.value = (self.value << 8) | b as u64;
+            self.remaining -= 1;
+            if self.remaining > 0 {
+                return Ok(None);
+            }
+            return Ok(Some(core::mem::take(&mut self.value)));
+        }
+        match b {
+            0..=0xf6 => Ok(Some(b as _)),
+            0xf7..=0xfe => {
+                self.remaining = b - 0xf7 + 1;
+                Ok(None)
+            }
+            0xff => Err(DecodeError::ReservedLeadByte { offset: 0 }),
+        }
+    }
+
+    /// Return `true` if some bytes of a value have been pushed but the
+    /// value is not complete.
+    pub fn is_partial(&self) -> bool {
+        self.remaining > 0
+    }
+}
+
+/// Decode values from chunks of arbitrary size, keeping an incomplete value
+/// at the end of a chunk for the next one.
+///
+/// Unlike [`BvarintDecodeState`], complete values inside a chunk are decoded
+/// directly from it.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub struct ResumableDecoder {
+    tail: [u8; MAX_ENCODED_LEN],
+    tail_len: u8,
+}
+
+#[cfg(feature = "alloc")]
+impl ResumableDecoder {
+    /// Create a decoder with nothing buffered.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the values completed by `chunk`.
+    ///
+    /// Return them, and the number of bytes of an incomplete value now
+    /// buffered for the next call.
+    ///
+    /// A `0xff` lead byte is an error, with the offset in `chunk`. The
+    /// stream is then corrupt, and the decoder should be discarded with it:
+    /// the values before the error in `chunk`, including one completed from
+    /// buffered bytes, are not returned, and the bytes after it are not
+    /// decoded. Nothing stays buffered, so a later `feed` decodes its chunk
+    /// as if it started a value.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(Vec<u64>, usize), DecodeError> {
+        let mut values = Vec::new();
+        let mut pos = 0;
+        if self.tail_len > 0 {
+            // A buffered lead byte is never 0xff, so `len` is positive.
+            let tail_len = self.tail_len as usize;
+            let len = peek_len(self.tail[0]);
+            pos = (len - tail_len).min(chunk.len());
+            self.tail[tail_len..tail_len + pos].copy_from_slice(&chunk[..pos]);
+            self.tail_len += pos as u8;
+            if (self.tail_len as usize) < len {
+                return Ok((values, self.tail_len as usize));
+            }
+            self.tail_len = 0;
+            values.push(decode_bvarint(&self.tail[..len])?.0);
+        }
+        while pos < chunk.len() {
+            match decode_bvarint(&chunk[pos..]) {
+                Ok((v, len)) => {
+                    values.push(v);
+                    pos += len;
+                }
+                Err(DecodeError::UnexpectedEof { .. }) => {
+                    let rest = &chunk[pos..];
+                    self.tail[..rest.len()].copy_from_slice(rest);
+                    self.tail_len = rest.len() as u8;
+                    break;
+                }
+                Err(e) => return Err(e.with_base_offset(pos)),
+            }
+        }
+        Ok((values, self.tail_len as usize))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::write_bvarint;
+
+    #[test]
+    fn test_push_byte() {
+        let mut state = BvarintDecodeState::new();
+        let mut buf = Vec::new();
+        for x in interesting_values() {
+            buf.clear();
+            write_bvarint(x, &mut buf).unwrap();
+            let (last, init) = buf.split_last().unwrap();
+            for &b in init {
+                assert_eq!(state.push_byte(b).unwrap(), None);
+                assert!(state.is_partial());
+            }
+            assert_eq!(state.push_byte(*last).unwrap(), Some(x));
+            assert!(!state.is_partial());
+        }
+    }
+
+    #[test]
+    fn test_reserved_lead() {
+        let mut state = BvarintDecodeState::new();
+        assert!(matches!(
+            state.push_byte(0xff),
+            Err(DecodeError::ReservedLeadByte { .. })
+        ));
+        assert_eq!(state.push_byte(3).unwrap(), Some(3));
+        // 0xff is a valid tail byte.
+        assert_eq!(state.push_byte(0xf7).unwrap(), None);
+        assert_eq!(state.push_byte(0xff).unwrap(), Some(0xff));
+    }
+
+    #[test]
+    fn test_resumable_splits() {
+        let values = interesting_values();
+        let buf = crate::encode_all_to_vec(&values);
+        for step in 1..=11 {
+            let mut decoder = ResumableDecoder::new();
+            let mut decoded = Vec::new();
+            for chunk in buf.chunks(step) {
+                let (v, buffered) = decoder.feed(chunk).unwrap();
+                assert!(buffered < MAX_ENCODED_LEN);
+                decoded.extend(v);
+            }
+            assert_eq!(decoded, values, "step {}", step);
+            assert_eq!(decoder.feed(&[]).unwrap(), (Vec::new(), 0));
+        }
+    }
+
+    #[test]
+    fn test_resumable_partial_and_error() {
+        let mut decoder = ResumableDecoder::new();
+        assert_eq!(decoder.feed(&[1, 0xf9, 0x12]).unwrap(), (vec![1], 2));
+        assert_eq!(decoder.feed(&[]).unwrap(), (vec![], 2));
+        assert_eq!(decoder.feed(&[0x34]).unwrap(), (vec![], 3));
+        assert_eq!(decoder.feed(&[0x56, 7]).unwrap(), (vec![0x123456, 7], 0));
+        assert!(matches!(
+            decoder.feed(&[2, 3, 0xff]),
+            Err(DecodeError::ReservedLeadByte { offset: 2 })
+        ));
+        assert_eq!(decoder.feed(&[4]).unwrap(), (vec![4], 0));
+    }
+
+    #[test]
+    fn test_resumable_error_after_tail() {
+        // The error comes after a value completed from buffered bytes, and
+        // after a value inside the chunk. Both are dropped.
+        let mut decoder = ResumableDecoder::new();
+        assert_eq!(decoder.feed(&[0xf8, 0x12]).unwrap(), (vec![], 2));
+        assert!(matches!(
+            decoder.feed(&[0x34, 5, 0xff, 6]),
+            Err(DecodeError::ReservedLeadByte { offset: 2 })
+        ));
+        assert_eq!(decoder.feed(&[]).unwrap(), (vec![], 0));
+        assert_eq!(decoder.feed(&[7]).unwrap(), (vec![7], 0));
+    }
+}
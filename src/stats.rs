@@ -0,0 +1,81 @@
+//! Encoded size statistics.
+
+use crate::encoded_len;
+use crate::MAX_ENCODED_LEN;
+
+/// How a set of values would encode.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Number of values.
+    pub count: usize,
+
+    /// Total encoded bytes of all values.
+    pub total_bytes: usize,
+
+    /// Number of values of each encoded width, indexed by width - 1.
+    pub width_counts: [usize; MAX_ENCODED_LEN],
+
+    /// The smallest value, or `None` if there are no values.
+    pub min: Option<u64>,
+
+    /// The largest value, or `None` if there are no values.
+    pub max: Option<u64>,
+}
+
+impl Stats {
+    /// Count `v`, encoded in `len` bytes.
+    pub(crate) fn record(&mut self, v: u64, len: usize) {
+        self.count += 1;
+        self.total_bytes += len;
+        self.width_counts[len - 1] += 1;
+        self.min = Some(self.min.map_or(v, |m| m.min(v)));
+        self.max = Some(self.max.map_or(v, |m| m.max(v)));
+    }
+
+    /// Average encoded bytes per value, or 0 if there are no values.
+    pub fn average_bytes(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.total_bytes as f64 / self.count as f64
+    }
+}
+
+/// Compute how `values` would encode, to compare with fixed-width or other
+/// encodings before choosing one.
+pub fn format_stats(values: &[u64]) -> Stats {
+    let mut stats = Stats::default();
+    for &v in values {
+        stats.record(v, encoded_len(v));
+    }
+    stats
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::encode_all_to_vec;
+    use crate::tests::interesting_values;
+
+    #[test]
+    fn test_format_stats() {
+        let values = [0, 0xf6, 0xf7, 0xff, 0x100, 0xffff, 0x10000, u64::MAX];
+        let stats = format_stats(&values);
+        assert_eq!(stats.count, 8);
+        assert_eq!(stats.total_bytes, 1 + 1 + 2 + 2 + 3 + 3 + 4 + 9);
+        assert_eq!(stats.width_counts, [2, 2, 2, 1, 0, 0, 0, 0, 1]);
+        assert_eq!(stats.average_bytes(), 25.0 / 8.0);
+        assert_eq!((stats.min, stats.max), (Some(0), Some(u64::MAX)));
+
+        assert_eq!(format_stats(&[]), Stats::default());
+        assert_eq!(format_stats(&[]).average_bytes(), 0.0);
+    }
+
+    #[test]
+    fn test_total_bytes() {
+        let values = interesting_values();
+        let stats = format_stats(&values);
+        assert_eq!(stats.total_bytes, encode_all_to_vec(&values).len());
+        assert_eq!(stats.width_counts.iter().sum::<usize>(), values.len());
+    }
+}
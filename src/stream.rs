@@ -0,0 +1,559 @@
+//! Adapters over `io::Read` and `io::Write`.
+
+use crate::decode_bvarint;
+use crate::encode_bvarint;
+use crate::peek_len;
+use crate::read_bvarint;
+use crate::read_bvarint_after_lead;
+use crate::DecodeError;
+use crate::Stats;
+use crate::MAX_ENCODED_LEN;
+use std::io;
+
+/// Read the lead byte. Return `None` on EOF before any byte is read.
+pub(crate) fn read_lead(mut r: impl io::Read) -> io::Result<Option<u8>> {
+    let mut lead = [0];
+    loop {
+        match r.read(&mut lead) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(lead[0])),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Read from `r` and return the decoded integer, or `None` on EOF before
+/// the first byte.
+///
+/// EOF after the lead byte is still an `UnexpectedEof` error, so "no more
+/// values" can be told apart from a truncated value.
+pub fn read_bvarint_opt(mut r: impl io::Read) -> io::Result<Option<u64>> {
+    match read_lead(&mut r)? {
+        Some(lead) => read_bvarint_after_lead(lead, r).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Read from `r` and return the decoded integer, decoding directly from
+/// the buffered bytes when they hold the whole value.
+///
+/// Values spanning the end of the buffer, and errors, go through
+/// [`read_bvarint`].
+pub fn read_bvarint_bufread(r: &mut impl io::BufRead) -> io::Result<u64> {
+    let buf = loop {
+        match r.fill_buf() {
+            Ok(buf) => break buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    };
+    if let Ok((v, len)) = decode_bvarint(buf) {
+        r.consume(len);
+        return Ok(v);
+    }
+    read_bvarint(r)
+}
+
+/// Iterate over integers decoded from an `io::Read`.
+///
+/// Iteration ends at EOF between values. EOF in the middle of a value is
+/// yielded as an error.
+pub struct BvarintReader<R> {
+    inner: R,
+}
+
+impl<R> BvarintReader<R> {
+    /// Wrap `r`.
+    pub fn new(r: R) -> Self {
+        Self { inner: r }
+    }
+
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> Iterator for BvarintReader<R> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_bvarint_opt(&mut self.inner).transpose()
+    }
+}
+
+/// Iterate over integers decoded from an `io::Read`, like
+/// [`BvarintReader`], while collecting [`Stats`] of the values read.
+///
+/// `total_bytes` and `width_counts` are of the bytes actually read, so an
+/// overlong encoding counts at its read length. Errors are not counted.
+pub struct StatReader<R> {
+    inner: R,
+    stats: Stats,
+}
+
+impl<R> StatReader<R> {
+    /// Wrap `r`, starting with empty `Stats`.
+    pub fn new(r: R) -> Self {
+        Self {
+            inner: r,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Statistics of the values yielded so far.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> Iterator for StatReader<R> {
+    type Item = io::Result<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lead = match read_lead(&mut self.inner) {
+            Ok(Some(lead)) => lead,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let result = read_bvarint_after_lead(lead, &mut self.inner);
+        if let Ok(v) = result {
+            self.stats.record(v, peek_len(lead));
+        }
+        Some(result)
+    }
+}
+
+/// Read integers from an `io::BufRead`, with one value of lookahead.
+///
+/// A peeked value that is fully buffered is decoded without consuming it.
+/// One that spans the end of the buffer has to be read, since `BufRead`
+/// cannot buffer more without consuming, and is held until
+/// [`next`](Self::next).
+///
+/// An invalid lead byte is reported without consuming anything. A value
+/// truncated by EOF is consumed, since reaching EOF needs a read.
+pub struct PeekableBvarintReader<R> {
+    inner: R,
+    /// The peeked value, and how many of its bytes are still unconsumed in
+    /// `inner`.
+    peeked: Option<(u64, usize)>,
+}
+
+impl<R: io::BufRead> PeekableBvarintReader<R> {
+    /// Wrap `r`, with nothing peeked yet.
+    pub fn new(r: R) -> Self {
+        Self {
+            inner: r,
+            peeked: None,
+        }
+    }
+
+    /// Return the next integer without advancing, or `None` on EOF between
+    /// values.
+    pub fn peek(&mut self) -> io::Result<Option<u64>> {
+        if let Some((v, _)) = self.peeked {
+            return Ok(Some(v));
+        }
+        let buf = loop {
+            match self.inner.fill_buf() {
+                Ok(buf) => break buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        };
+        let peeked = match decode_bvarint(buf) {
+            Ok((v, len)) => (v, len),
+            // Only a value spanning the end of the buffer needs reading.
+            // Other errors are returned without consuming, so they repeat.
+            Err(DecodeError::UnexpectedEof { .. }) => match read_bvarint_opt(&mut self.inner)? {
+                Some(v) => (v, 0),
+                None => return Ok(None),
+            },
+            Err(e) => return Err(e.into()),
+        };
+        self.peeked = Some(peeked);
+        Ok(Some(peeked.0))
+    }
+
+    /// Return the next integer and advance past it, or `None` on EOF
+    /// between values.
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> io::Result<Option<u64>> {
+        let v = self.peek()?;
+        if let Some((_, unconsumed)) = self.peeked.take() {
+            self.inner.consume(unconsumed);
+        }
+        Ok(v)
+    }
+}
+
+/// Write integers to an `io::Write` and count the bytes written.
+pub struct BvarintWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: io::Write> BvarintWriter<W> {
+    /// Wrap `w`, with no bytes written yet.
+    pub fn new(w: W) -> Self {
+        Self {
+            inner: w,
+            written: 0,
+        }
+    }
+
+    /// Encode `v` and write it.
+    pub fn write(&mut self, v: u64) -> io::Result<()> {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode_bvarint(v, &mut buf).expect("9 bytes fit any u64");
+        self.inner.write_all(&buf[..len])?;
+        self.written += len as u64;
+        Ok(())
+    }
+
+    /// Total bytes written so far. This is the offset of the next value.
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Return the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+/// A typed sink of integers over an `io::Write`.
+///
+/// The inner writer is flushed on drop. Errors from that flush are ignored,
+/// so call [`flush`](Self::flush) to observe them.
+pub struct BvarintSink<W: io::Write> {
+    inner: W,
+}
+
+impl<W: io::Write> BvarintSink<W> {
+    /// Wrap `w`.
+    pub fn new(w: W) -> Self {
+        Self { inner: w }
+    }
+
+    /// Encode `v` and write it.
+    pub fn send(&mut self, v: u64) -> io::Result<()> {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode_bvarint(v, &mut buf).expect("9 bytes fit any u64");
+        self.inner.write_all(&buf[..len])
+    }
+
+    /// Flush the inner writer, returning any error that drop would ignore.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: io::Write> Drop for BvarintSink<W> {
+    fn drop(&mut self) {
+        let _ = self.inner.flush();
+    }
+}
+
+/// A typed source of integers over an `io::Read`.
+pub struct BvarintSource<R> {
+    inner: R,
+}
+
+impl<R: io::Read> BvarintSource<R> {
+    /// Wrap `r`.
+    pub fn new(r: R) -> Self {
+        Self { inner: r }
+    }
+
+    /// Read the next integer. Return `None` on EOF between values.
+    pub fn recv(&mut self) -> io::Result<Option<u64>> {
+        read_bvarint_opt(&mut self.inner)
+    }
+
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Write integers to a seekable writer and record the offset of each.
+///
+/// Offsets are absolute positions in `W`, starting from its position when
+/// the writer is created.
+pub struct IndexedBvarintWriter<W: io::Write + io::Seek> {
+    inner: BvarintWriter<W>,
+    start: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: io::Write + io::Seek> IndexedBvarintWriter<W> {
+    /// Wrap `w`. Offsets start from its current position, read with
+    /// `stream_position`, which can fail.
+    pub fn new(mut w: W) -> io::Result<Self> {
+        let start = w.stream_position()?;
+        Ok(Self {
+            inner: BvarintWriter::new(w),
+            start,
+            offsets: Vec::new(),
+        })
+    }
+
+    /// Encode `v` and write it. Return the offset it was written at.
+    pub fn write(&mut self, v: u64) -> io::Result<u64> {
+        let offset = self.start + self.inner.bytes_written();
+        self.inner.write(v)?;
+        self.offsets.push(offset);
+        Ok(offset)
+    }
+
+    /// Return the writer and the offsets of all values written.
+    pub fn finish(self) -> io::Result<(W, Vec<u64>)> {
+        let mut w = self.inner.into_inner();
+        w.flush()?;
+        Ok((w, self.offsets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoded_len;
+    use crate::tests::interesting_values;
+    use crate::write_bvarint;
+
+    #[test]
+    fn test_read_opt() {
+        assert_eq!(read_bvarint_opt(&[][..]).unwrap(), None);
+        assert_eq!(
+            read_bvarint_opt(&[0xf8, 0x12, 0x34][..]).unwrap(),
+            Some(0x1234)
+        );
+        let err = read_bvarint_opt(&[0xf8, 0x12][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_reader_round_trip() {
+        let values = interesting_values();
+        let mut buf = Vec::new();
+        for &v in &values {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        let decoded: Vec<u64> = BvarintReader::new(&buf[..])
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(decoded, values);
+        assert!(BvarintReader::new(&[][..]).next().is_none());
+    }
+
+    #[test]
+    fn test_reader_truncated() {
+        let mut buf = Vec::new();
+        write_bvarint(1, &mut buf).unwrap();
+        write_bvarint(0x1234, &mut buf).unwrap();
+        buf.pop();
+        let mut reader = BvarintReader::new(&buf[..]);
+        assert_eq!(reader.next().unwrap().unwrap(), 1);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_stat_reader() {
+        let mut buf = Vec::new();
+        for v in [0x1234, 5, 0xf7, 0x12345678] {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        // An overlong 1 counts as 2 bytes read.
+        buf.extend_from_slice(&[0xf7, 0x01, 0xf8, 0x12]);
+        let mut reader = StatReader::new(&buf[..]);
+        let decoded: Vec<u64> = reader.by_ref().take(5).map(Result::unwrap).collect();
+        assert_eq!(decoded, [0x1234, 5, 0xf7, 0x12345678, 1]);
+        let err = reader.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+
+        let stats = reader.stats();
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.total_bytes, 3 + 1 + 2 + 5 + 2);
+        assert_eq!(stats.width_counts, [1, 2, 1, 0, 1, 0, 0, 0, 0]);
+        assert_eq!((stats.min, stats.max), (Some(1), Some(0x12345678)));
+        let mut empty = StatReader::new(&[][..]);
+        assert!(empty.next().is_none());
+        assert_eq!(empty.stats(), &Stats::default());
+    }
+
+    #[test]
+    fn test_read_bufread() {
+        let values = interesting_values();
+        let mut buf = Vec::new();
+        for &v in &values {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        // A capacity of 5 makes many values span the buffer boundary.
+        for &capacity in &[1, 5, 4096] {
+            let mut r = io::BufReader::with_capacity(capacity, &buf[..]);
+            for &v in &values {
+                assert_eq!(read_bvarint_bufread(&mut r).unwrap(), v);
+            }
+            let err = read_bvarint_bufread(&mut r).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+        let mut r = io::BufReader::new(&[0xff, 1][..]);
+        assert!(read_bvarint_bufread(&mut r).is_err());
+        assert_eq!(read_bvarint_bufread(&mut r).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_peekable() {
+        let values = interesting_values();
+        let mut buf = Vec::new();
+        for &v in &values {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        // A small buffer makes many values span its end.
+        for capacity in [1, 4, 9, 4096] {
+            let mut r =
+                PeekableBvarintReader::new(io::BufReader::with_capacity(capacity, &buf[..]));
+            for &v in &values {
+                assert_eq!(r.peek().unwrap(), Some(v));
+                assert_eq!(r.peek().unwrap(), Some(v));
+                assert_eq!(r.next().unwrap(), Some(v));
+            }
+            assert_eq!(r.peek().unwrap(), None);
+            assert_eq!(r.next().unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn test_peek_does_not_consume() {
+        let mut inner = &[0xf8, 0x12, 0x34, 5][..];
+        let mut r = PeekableBvarintReader::new(&mut inner);
+        assert_eq!(r.peek().unwrap(), Some(0x1234));
+        assert_eq!(r.inner.len(), 4);
+        assert_eq!(r.next().unwrap(), Some(0x1234));
+        assert_eq!(r.next().unwrap(), Some(5));
+
+        let mut r = PeekableBvarintReader::new(&[0xf8, 0x12][..]);
+        let err = r.peek().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_peek_reserved_repeats() {
+        let mut r = PeekableBvarintReader::new(&[0xff, 1][..]);
+        for _ in 0..2 {
+            let err = DecodeError::from(r.peek().unwrap_err());
+            assert!(matches!(err, DecodeError::ReservedLeadByte { offset: 0 }));
+        }
+        let err = DecodeError::from(r.next().unwrap_err());
+        assert!(matches!(err, DecodeError::ReservedLeadByte { offset: 0 }));
+        assert_eq!(r.inner, [0xff, 1]);
+    }
+
+    /// Return `Interrupted` before each byte.
+    struct Interrupting<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl io::Read for Interrupting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(1);
+            (&mut self.data).read(&mut buf[..n])
+        }
+    }
+
+    #[test]
+    fn test_interrupted() {
+        let values = interesting_values();
+        let mut buf = Vec::new();
+        for &v in &values {
+            write_bvarint(v, &mut buf).unwrap();
+        }
+        let new = || Interrupting {
+            data: &buf,
+            interrupt: false,
+        };
+
+        let mut r = new();
+        for &v in &values {
+            assert_eq!(read_bvarint(&mut r).unwrap(), v);
+        }
+        assert!(read_bvarint_opt(&mut r).unwrap().is_none());
+
+        let decoded: io::Result<Vec<u64>> = BvarintReader::new(new()).collect();
+        assert_eq!(decoded.unwrap(), values);
+
+        let mut r = io::BufReader::with_capacity(4, new());
+        for &v in &values {
+            assert_eq!(read_bvarint_bufread(&mut r).unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_sink_source() {
+        let mut buf = Vec::new();
+        {
+            let mut sink = BvarintSink::new(io::BufWriter::new(&mut buf));
+            for x in interesting_values() {
+                sink.send(x).unwrap();
+            }
+        }
+        let mut source = BvarintSource::new(&buf[..]);
+        for x in interesting_values() {
+            assert_eq!(source.recv().unwrap(), Some(x));
+        }
+        assert_eq!(source.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn test_indexed_writer() {
+        use std::io::Seek;
+
+        let mut cursor = io::Cursor::new(vec![0xaa; 3]);
+        cursor.seek(io::SeekFrom::End(0)).unwrap();
+        let mut writer = IndexedBvarintWriter::new(cursor).unwrap();
+        for x in interesting_values() {
+            writer.write(x).unwrap();
+        }
+        let (mut cursor, offsets) = writer.finish().unwrap();
+        assert_eq!(offsets.len(), interesting_values().len());
+        assert_eq!(offsets[0], 3);
+        for (x, offset) in interesting_values().into_iter().zip(offsets).rev() {
+            cursor.seek(io::SeekFrom::Start(offset)).unwrap();
+            assert_eq!(read_bvarint(&mut cursor).unwrap(), x);
+        }
+    }
+
+    #[test]
+    fn test_writer_offsets() {
+        let mut writer = BvarintWriter::new(Vec::new());
+        let mut offsets = Vec::new();
+        let mut expected = 0;
+        for x in interesting_values() {
+            offsets.push(writer.bytes_written());
+            writer.write(x).unwrap();
+            expected += encoded_len(x) as u64;
+            assert_eq!(writer.bytes_written(), expected);
+        }
+        let buf = writer.into_inner();
+        assert_eq!(buf.len() as u64, expected);
+        for (x, offset) in interesting_values().into_iter().zip(offsets) {
+            assert_eq!(read_bvarint(&buf[offset as usize..]).unwrap(), x);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Boundary values for testing code that embeds bvarints.
+//!
+//! Values are never removed from these sets, so tests built on them keep
+//! their coverage across releases.
+
+use crate::ONE_BYTE_MAX;
+use alloc::vec::Vec;
+
+/// Return `u64` values around every width transition, sorted and without
+/// duplicates.
+///
+/// This is each power of two and its neighbours, the 1-byte limit
+/// [`ONE_BYTE_MAX`] and its neighbours, and `u64::MAX`. The width maxes are
+/// among them, since they are powers of two minus one.
+///
+/// ```
+/// use bvarint::test_util::interesting_values;
+///
+/// for v in interesting_values() {
+///     let mut buf = Vec::new();
+///     bvarint::write_bvarint(v, &mut buf).unwrap();
+///     assert_eq!(bvarint::read_bvarint(&buf[..]).unwrap(), v);
+/// }
+/// ```
+pub fn interesting_values() -> Vec<u64> {
+    let mut values: Vec<u64> = (0..64)
+        .flat_map(|b| {
+            let p = 1u64 << b;
+            [p - 1, p, p + 1]
+        })
+        .chain([ONE_BYTE_MAX - 1, ONE_BYTE_MAX, ONE_BYTE_MAX + 1])
+        .chain([u64::MAX - 1, u64::MAX])
+        .collect();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+/// Return [`interesting_values`] as `u128`, plus the same kind of values
+/// above `u64::MAX`, for the `u128` extension. Sorted and without
+/// duplicates.
+pub fn interesting_values_u128() -> Vec<u128> {
+    let mut values: Vec<u128> = interesting_values()
+        .into_iter()
+        .map(u128::from)
+        .chain((64..128).flat_map(|b| {
+            let p = 1u128 << b;
+            [p - 1, p, p + 1]
+        }))
+        .chain([u128::MAX - 1, u128::MAX])
+        .collect();
+    values.sort_unstable();
+    values.dedup();
+    values
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::value_range_for_width;
+
+    #[test]
+    fn test_covers_width_transitions() {
+        let values = interesting_values();
+        for width in 1..=9 {
+            let range = value_range_for_width(width).unwrap();
+            assert!(values.contains(range.start()), "width {}", width);
+            assert!(values.contains(range.end()), "width {}", width);
+        }
+        assert!(values.windows(2).all(|w| w[0] < w[1]));
+
+        let wide = interesting_values_u128();
+        assert!(wide.contains(&(u64::MAX as u128 + 1)));
+        assert!(wide.contains(&u128::MAX));
+        assert!(wide.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,57 @@
+//! Abstracting over varint schemes.
+
+use crate::Bvarint;
+use std::io;
+
+/// A varint scheme, so code can be generic over the encoding.
+///
+/// The methods take no `self`, so the trait is not object safe. Use it as a
+/// type parameter, like `fn save<C: VarintCodec>(...)`, and pick the scheme
+/// at the call site with `save::<Bvarint>(...)`.
+pub trait VarintCodec {
+    /// Encode `v` and write it to `w`.
+    fn write(v: u64, w: impl io::Write) -> io::Result<()>;
+
+    /// Read from `r` and return the decoded integer.
+    fn read(r: impl io::Read) -> io::Result<u64>;
+
+    /// Return the number of bytes `write` would emit for `v`.
+    fn encoded_len(v: u64) -> usize;
+}
+
+impl VarintCodec for Bvarint {
+    fn write(v: u64, w: impl io::Write) -> io::Result<()> {
+        crate::write_bvarint(v, w)
+    }
+
+    fn read(r: impl io::Read) -> io::Result<u64> {
+        crate::read_bvarint(r)
+    }
+
+    fn encoded_len(v: u64) -> usize {
+        crate::encoded_len(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+
+    fn round_trip<C: VarintCodec>(values: &[u64]) -> Vec<u64> {
+        let mut buf = Vec::new();
+        for &v in values {
+            C::write(v, &mut buf).unwrap();
+        }
+        let total: usize = values.iter().map(|&v| C::encoded_len(v)).sum();
+        assert_eq!(buf.len(), total);
+        let mut r = &buf[..];
+        values.iter().map(|_| C::read(&mut r).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_generic() {
+        let values = interesting_values();
+        assert_eq!(round_trip::<Bvarint>(&values), values);
+    }
+}
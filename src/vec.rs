@@ -0,0 +1,279 @@
+use crate::count_values;
+use crate::decode_bvarint;
+use crate::encode_bvarint;
+use crate::encode_to_stack;
+use crate::encoded_len;
+use crate::peek_len;
+use crate::read_bvarint_exact;
+use crate::DecodeError;
+use crate::MAX_ENCODED_LEN;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// Append bvarint encodings to a `Vec<u8>` without going through `io::Write`.
+///
+/// With the `smallvec` feature, this is also implemented for `SmallVec`.
+pub trait BvarintVecExt {
+    /// Encode `v` and append it.
+    fn push_bvarint(&mut self, v: u64);
+}
+
+impl BvarintVecExt for Vec<u8> {
+    fn push_bvarint(&mut self, v: u64) {
+        let mut buf = [0; MAX_ENCODED_LEN];
+        let len = encode_bvarint(v, &mut buf).expect("9 bytes fit any u64");
+        self.extend_from_slice(&buf[..len]);
+    }
+}
+
+/// Encode `values` back-to-back into a new `Vec`.
+///
+/// The `Vec` is allocated once with the exact encoded size.
+pub fn encode_all_to_vec(values: &[u64]) -> Vec<u8> {
+    let len = values.iter().map(|&v| encoded_len(v)).sum();
+    let mut buf = Vec::with_capacity(len);
+    for &v in values {
+        buf.push_bvarint(v);
+    }
+    buf
+}
+
+/// Decode all values from `buf`, which must contain only complete values.
+///
+/// On error, the offset in `UnexpectedEof` and `ReservedLeadByte` is where
+/// the bad value starts in `buf`.
+pub fn decode_all_from_slice(buf: &[u8]) -> Result<Vec<u64>, DecodeError> {
+    let mut values = Vec::new();
+    decode_all_into(buf, &mut values)?;
+    Ok(values)
+}
+
+/// Decode all values from `buf` and append them to `out`.
+///
+/// Return the number of values appended. `out` is reserved once using
+/// [`count_values`], and is unchanged on error.
+pub fn decode_all_into(buf: &[u8], out: &mut Vec<u64>) -> Result<usize, DecodeError> {
+    let count = count_values(buf)?;
+    out.reserve(count);
+    let mut rest = buf;
+    for _ in 0..count {
+        let (v, len) = decode_bvarint(rest).expect("checked by count_values");
+        out.push(v);
+        rest = &rest[len..];
+    }
+    Ok(count)
+}
+
+/// Decode the single value in `buf` and return its canonical encoding.
+///
+/// This fixes overlong encodings, like `[0xf7, 0x01]` for 1, from other
+/// encoders. A canonical `buf` is returned unchanged. Bytes after the
+/// value are an error.
+pub fn shrink_to_canonical(buf: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let v = read_bvarint_exact(buf)?;
+    let (buf, len) = encode_to_stack(v);
+    Ok(buf[..len].to_vec())
+}
+
+/// Encode `v` and append it to the back of `buf`.
+pub fn push_bvarint_deque(buf: &mut VecDeque<u8>, v: u64) {
+    let (encoded, len) = encode_to_stack(v);
+    buf.extend(&encoded[..len]);
+}
+
+/// Decode a value from the front of `buf` and remove its bytes.
+///
+/// Return `Ok(None)` if `buf` holds only part of a value, or nothing. No
+/// bytes are removed then, so push more and call again. On error, no bytes
+/// are removed either.
+pub fn read_bvarint_deque(buf: &mut VecDeque<u8>) -> Result<Option<u64>, DecodeError> {
+    let lead = match buf.front() {
+        Some(&lead) => lead,
+        None => return Ok(None),
+    };
+    if lead == 0xff {
+        return Err(DecodeError::ReservedLeadByte { offset: 0 });
+    }
+    let len = peek_len(lead);
+    if buf.len() < len {
+        return Ok(None);
+    }
+    // The value may wrap around the end of the ring, so copy it out.
+    let mut a = [0; MAX_ENCODED_LEN];
+    for (dst, &src) in a.iter_mut().zip(buf.iter()).take(len) {
+        *dst = src;
+    }
+    let (v, _) = decode_bvarint(&a[..len])?;
+    buf.drain(..len);
+    Ok(Some(v))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+    use crate::write_bvarint;
+
+    fn check_push_u64(x: u64) {
+        let mut expected = vec![1, 2];
+        write_bvarint(x, &mut expected).unwrap();
+        let mut buf = vec![1, 2];
+        buf.push_bvarint(x);
+        assert_eq!(buf, expected, "check_push_u64(0x{:x})", x);
+    }
+
+    #[test]
+    fn test_push_u64_manual() {
+        for x in interesting_values() {
+            check_push_u64(x);
+        }
+    }
+
+    #[test]
+    fn test_encode_all_to_vec() {
+        let values = interesting_values();
+        let buf = encode_all_to_vec(&values);
+        let mut expected = Vec::new();
+        for &x in &values {
+            write_bvarint(x, &mut expected).unwrap();
+        }
+        assert_eq!(buf, expected);
+        assert_eq!(buf.capacity(), buf.len());
+        assert!(encode_all_to_vec(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_decode_all_from_slice() {
+        let values = interesting_values();
+        let mut buf = encode_all_to_vec(&values);
+        assert_eq!(decode_all_from_slice(&buf).unwrap(), values);
+        assert!(decode_all_from_slice(&[]).unwrap().is_empty());
+
+        let offset = buf.len();
+        buf.extend_from_slice(&[0xf9, 1, 2]);
+        assert!(matches!(
+            decode_all_from_slice(&buf),
+            Err(DecodeError::UnexpectedEof { offset: o }) if o == offset
+        ));
+    }
+
+    #[test]
+    fn test_decode_all_overflow_offset() {
+        let mut buf = encode_all_to_vec(&[1, 0x1234, 0x12345678]);
+        let offset = buf.len();
+        buf.push(0xff);
+        buf.extend_from_slice(&encode_all_to_vec(&[5, 6]));
+        let err = decode_all_from_slice(&buf).unwrap_err();
+        assert!(matches!(err, DecodeError::ReservedLeadByte { offset: o } if o == offset));
+        assert_eq!(err.to_string(), "exceeds u64::MAX (value at offset 9)");
+    }
+
+    #[test]
+    fn test_decode_all_into() {
+        let mut out = vec![7, 8];
+        let buf = encode_all_to_vec(&interesting_values());
+        let count = decode_all_into(&buf, &mut out).unwrap();
+        assert_eq!(count, interesting_values().len());
+        assert_eq!(out[..2], [7, 8]);
+        assert_eq!(out[2..], interesting_values()[..]);
+
+        let mut out = vec![7, 8];
+        assert!(decode_all_into(&[1, 2, 0xf8, 0], &mut out).is_err());
+        assert_eq!(out, [7, 8]);
+    }
+
+    #[test]
+    fn test_shrink_to_canonical() {
+        for x in interesting_values() {
+            let buf = encode_all_to_vec(&[x]);
+            assert_eq!(shrink_to_canonical(&buf).unwrap(), buf);
+        }
+        for (overlong, canonical) in &[
+            (&[0xf7, 0x01][..], &[0x01][..]),
+            (&[0xf8, 0x00, 0xff], &[0xf7, 0xff]),
+            (
+                &[0xfe, 0, 0, 0, 0, 0, 0x01, 0x00, 0x00],
+                &[0xf9, 0x01, 0x00, 0x00],
+            ),
+        ] {
+            assert_eq!(shrink_to_canonical(overlong).unwrap(), *canonical);
+        }
+        assert!(matches!(
+            shrink_to_canonical(&[0x01, 0x02]),
+            Err(DecodeError::TrailingBytes { .. })
+        ));
+        assert!(matches!(
+            shrink_to_canonical(&[0xf8, 0x01]),
+            Err(DecodeError::UnexpectedEof { .. })
+        ));
+    }
+
+    #[test]
+    fn test_deque_round_trip() {
+        let values = interesting_values();
+        let mut buf = VecDeque::new();
+        for &v in &values {
+            push_bvarint_deque(&mut buf, v);
+        }
+        assert_eq!(
+            buf.iter().copied().collect::<Vec<u8>>(),
+            encode_all_to_vec(&values)
+        );
+        for &v in &values {
+            assert_eq!(read_bvarint_deque(&mut buf).unwrap(), Some(v));
+        }
+        assert!(buf.is_empty());
+        assert_eq!(read_bvarint_deque(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn test_deque_partial() {
+        // Rotate the ring so later values can wrap around its end.
+        let mut buf = VecDeque::with_capacity(4);
+        buf.extend(&[0, 0, 0]);
+        buf.drain(..3);
+        push_bvarint_deque(&mut buf, 7);
+        buf.extend(&[0xf9, 0x01]);
+        assert_eq!(read_bvarint_deque(&mut buf).unwrap(), Some(7));
+        assert_eq!(read_bvarint_deque(&mut buf).unwrap(), None);
+        assert_eq!(buf, [0xf9, 0x01]);
+        buf.extend(&[0x23, 0x45, 9]);
+        assert_eq!(read_bvarint_deque(&mut buf).unwrap(), Some(0x12345));
+        assert_eq!(buf, [9]);
+
+        let mut buf = VecDeque::from(vec![0xff, 1]);
+        assert!(matches!(
+            read_bvarint_deque(&mut buf),
+            Err(DecodeError::ReservedLeadByte { offset: 0 })
+        ));
+        assert_eq!(buf.len(), 2);
+    }
+
+    fn check_round_trip_all(values: Vec<u64>) {
+        let buf = encode_all_to_vec(&values);
+        assert_eq!(decode_all_from_slice(&buf).unwrap(), values);
+    }
+
+    #[test]
+    fn test_round_trip_all_quickcheck() {
+        quickcheck(check_round_trip_all as fn(Vec<u64>));
+    }
+
+    /// Random `u64`s are almost all 9 bytes long, so shift them by random
+    /// amounts to mix every width in one buffer.
+    fn check_round_trip_all_mixed(values: Vec<(u64, u8)>) {
+        let values: Vec<u64> = values.into_iter().map(|(v, s)| v >> (s % 64)).collect();
+        check_round_trip_all(values);
+    }
+
+    #[test]
+    fn test_round_trip_all_mixed_quickcheck() {
+        quickcheck(check_round_trip_all_mixed as fn(Vec<(u64, u8)>));
+    }
+
+    #[test]
+    fn test_push_u64_quickcheck() {
+        quickcheck(check_push_u64 as fn(u64));
+    }
+}
@@ -0,0 +1,74 @@
+//! JavaScript bindings via `wasm-bindgen`.
+//!
+//! JavaScript numbers are `f64`, which represent every integer up to
+//! `2^53` exactly. Larger integers are still accepted by [`encode`], but
+//! only those representable as `f64` can be passed in, and [`decode`]
+//! rounds values above `2^53` to the nearest `f64`.
+
+use crate::decode_bvarint;
+use crate::encode_to_stack;
+use wasm_bindgen::prelude::*;
+
+/// Convert `v` to `u64` if it is a non-negative integer below `2^64`.
+fn f64_to_u64(v: f64) -> Option<u64> {
+    // 2^64 is exactly representable, u64::MAX is not.
+    if (0.0..18446744073709551616.0).contains(&v) && v.fract() == 0.0 {
+        Some(v as u64)
+    } else {
+        None
+    }
+}
+
+/// Encode the non-negative integer `v`.
+#[wasm_bindgen]
+pub fn encode(v: f64) -> Result<Vec<u8>, JsError> {
+    let v =
+        f64_to_u64(v).ok_or_else(|| JsError::new("expected a non-negative integer below 2^64"))?;
+    let (buf, len) = encode_to_stack(v);
+    Ok(buf[..len].to_vec())
+}
+
+/// Decode an integer from the front of `bytes`.
+///
+/// Values above `2^53` are rounded to the nearest `f64`.
+#[wasm_bindgen]
+pub fn decode(bytes: &[u8]) -> Result<f64, JsError> {
+    let (v, _) = decode_bvarint(bytes).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(v as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+    #[test]
+    fn test_f64_to_u64() {
+        assert_eq!(f64_to_u64(0.0), Some(0));
+        assert_eq!(f64_to_u64(MAX_SAFE_INTEGER), Some((1 << 53) - 1));
+        assert_eq!(f64_to_u64(18446744073709549568.0), Some(u64::MAX - 2047));
+        assert_eq!(f64_to_u64(18446744073709551616.0), None);
+        assert_eq!(f64_to_u64(-1.0), None);
+        assert_eq!(f64_to_u64(1.5), None);
+        assert_eq!(f64_to_u64(f64::NAN), None);
+        assert_eq!(f64_to_u64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &v in &[0.0, 246.0, 247.0, 65536.0, MAX_SAFE_INTEGER] {
+            let bytes = encode(v).unwrap();
+            assert_eq!(decode(&bytes).unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn test_precision_boundary() {
+        // 2^53 + 1 is not representable and rounds to 2^53.
+        let (buf, len) = encode_to_stack((1 << 53) + 1);
+        assert_eq!(decode(&buf[..len]).unwrap(), 9007199254740992.0);
+        let (buf, len) = encode_to_stack(u64::MAX);
+        assert_eq!(decode(&buf[..len]).unwrap(), 18446744073709551616.0);
+    }
+}
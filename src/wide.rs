@@ -0,0 +1,487 @@
+//! Integers wider than `u64`, using the reserved `0xff` lead byte.
+//!
+//! Values that fit in `u64` are encoded exactly like [`write_bvarint`].
+//! Larger values are encoded as `0xff`, the byte length as a bvarint, then
+//! the big-endian bytes without leading zeros. The length is always larger
+//! than 8, so the `memcmp` order is preserved across the full range, and
+//! longer magnitudes sort after shorter ones.
+//!
+//! For `u128` the length is in `9..=16` and takes a single byte. Big
+//! integers of any size use the same scheme, so a `u128` encoding is also
+//! a valid big integer encoding.
+
+use crate::read_bvarint;
+use crate::read_bvarint_after_lead;
+use crate::write_bvarint;
+use std::io;
+use std::io::Read;
+
+/// Encode `v` and write it to `w`.
+///
+/// Values that fit in `u64` never use the `0xff` prefix, so streams can mix
+/// `u64` and `u128` values, and [`read_bvarint`](crate::read_bvarint) can
+/// read the former.
+pub fn write_bvarint_u128(v: u128, mut w: impl io::Write) -> io::Result<()> {
+    if v <= u64::MAX as u128 {
+        return write_bvarint(v as u64, w);
+    }
+    let width = ((128 + 8 - 1 - v.leading_zeros()) / 8) as usize;
+    debug_assert!(width > 8);
+    let a: [u8; 16] = v.to_be_bytes();
+    w.write_all(&[0xff, width as u8])?;
+    w.write_all(&a[(16 - width)..])?;
+    Ok(())
+}
+
+/// Encode `v` clamped to `u64::MAX` and write it to `w`.
+///
+/// This is lossy: values above `u64::MAX` are written as `u64::MAX`, which
+/// plain [`read_bvarint`] can read. Return `true` if `v` was clamped. Use
+/// [`write_bvarint_u128`] to keep the exact value.
+pub fn write_bvarint_saturating_u128(v: u128, w: impl io::Write) -> io::Result<bool> {
+    let saturated = v > u64::MAX as u128;
+    write_bvarint(v.min(u64::MAX as u128) as u64, w)?;
+    Ok(saturated)
+}
+
+/// Read from `r` and return the decoded integer.
+///
+/// After `0xff`, a length that fits in `u64` or a leading zero byte is
+/// `InvalidData`, so each value has one encoding and the order holds.
+pub fn read_bvarint_u128(mut r: impl io::Read) -> io::Result<u128> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    if lead[0] != 0xff {
+        return Ok(read_bvarint_after_lead(lead[0], r)? as u128);
+    }
+    let mut width = [0];
+    r.read_exact(&mut width)?;
+    let width = width[0] as usize;
+    if width > 16 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "exceeds u128::MAX",
+        ));
+    }
+    if width <= 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("u128 length {} fits in u64", width),
+        ));
+    }
+    let mut a = [0; 16];
+    r.read_exact(&mut a[(16 - width)..])?;
+    if a[16 - width] == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "u128 has leading zeros",
+        ));
+    }
+    Ok(u128::from_be_bytes(a))
+}
+
+/// Encode `v` in the compact `u64` form when it fits, and the `0xff`
+/// prefixed form otherwise, and write it to `w`.
+///
+/// This is [`write_bvarint_u128`], named for streams that mix `u64` and
+/// `u128` values: [`read_bvarint`] reads the values that fit in `u64`.
+pub fn write_bvarint_auto(v: u128, w: impl io::Write) -> io::Result<()> {
+    write_bvarint_u128(v, w)
+}
+
+/// Read a value written by [`write_bvarint_auto`] or [`write_bvarint`]
+/// from `r`.
+///
+/// This is [`read_bvarint_u128`].
+pub fn read_bvarint_auto(r: impl io::Read) -> io::Result<u128> {
+    read_bvarint_u128(r)
+}
+
+/// Decoder options for the reserved `0xff` lead byte.
+///
+/// The default is strict: only values that fit in `u64` are accepted, like
+/// [`read_bvarint`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BvarintConfig {
+    /// Decode `0xff` as the `u128` extension instead of rejecting it.
+    pub allow_u128: bool,
+}
+
+impl BvarintConfig {
+    /// Read from `r` and return the decoded integer.
+    ///
+    /// Unless `allow_u128` is set, a `0xff` lead byte is
+    /// [`DecodeError::ReservedLeadByte`], and nothing after it is read.
+    ///
+    /// [`DecodeError::ReservedLeadByte`]: crate::DecodeError::ReservedLeadByte
+    pub fn read_u128(&self, r: impl io::Read) -> io::Result<u128> {
+        if self.allow_u128 {
+            read_bvarint_u128(r)
+        } else {
+            read_bvarint(r).map(|v| v as u128)
+        }
+    }
+}
+
+/// Encode the non-negative integer with big-endian bytes `be_bytes` and
+/// write it to `w`.
+///
+/// Leading zero bytes are ignored. Values that fit in `u64` are encoded
+/// like [`write_bvarint`].
+pub fn write_bvarint_bytes_bigint(be_bytes: &[u8], mut w: impl io::Write) -> io::Result<()> {
+    let start = be_bytes
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(be_bytes.len());
+    let bytes = &be_bytes[start..];
+    if bytes.len() <= 8 {
+        let mut a = [0; 8];
+        a[(8 - bytes.len())..].copy_from_slice(bytes);
+        return write_bvarint(u64::from_be_bytes(a), w);
+    }
+    w.write_all(&[0xff])?;
+    write_bvarint(bytes.len() as u64, &mut w)?;
+    w.write_all(bytes)
+}
+
+/// Read a value written by [`write_bvarint_bytes_bigint`] or
+/// [`write_bvarint_u128`] from `r`.
+///
+/// Return the big-endian bytes without leading zeros. Zero is returned as
+/// an empty `Vec`.
+pub fn read_bvarint_bigint(mut r: impl io::Read) -> io::Result<Vec<u8>> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    if lead[0] != 0xff {
+        let v = read_bvarint_after_lead(lead[0], r)?;
+        let bytes = v.to_be_bytes();
+        let start = (v.leading_zeros() / 8) as usize;
+        return Ok(bytes[start..].to_vec());
+    }
+    read_bigint_after_lead(r)
+}
+
+/// Read from `r` and return either a `u64`, or the big-endian bytes of a
+/// larger integer behind the `0xff` lead byte.
+///
+/// The bytes are returned like [`read_bvarint_bigint`] would, so they can
+/// be handed to a bignum library.
+pub fn read_bvarint_or_overflow(mut r: impl io::Read) -> io::Result<Result<u64, Vec<u8>>> {
+    let mut lead = [0];
+    r.read_exact(&mut lead)?;
+    if lead[0] != 0xff {
+        return read_bvarint_after_lead(lead[0], r).map(Ok);
+    }
+    read_bigint_after_lead(r).map(Err)
+}
+
+/// Read the length and bytes of a big integer after the `0xff` lead byte.
+fn read_bigint_after_lead(mut r: impl io::Read) -> io::Result<Vec<u8>> {
+    let len = read_bvarint(&mut r)?;
+    if len <= 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("big integer length {} fits in u64", len),
+        ));
+    }
+    let mut bytes = Vec::new();
+    r.take(len).read_to_end(&mut bytes)?;
+    if (bytes.len() as u64) < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    if bytes[0] == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "big integer has leading zeros",
+        ));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_bvarint;
+    use crate::tests::interesting_values;
+    use crate::tests::quickcheck;
+
+    fn encode(x: u128) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_bvarint_u128(x, &mut buf).unwrap();
+        buf
+    }
+
+    fn check_round_trip_u128(x: u128) {
+        let buf = encode(x);
+        let y = read_bvarint_u128(&buf[..]).unwrap();
+        assert_eq!(x, y, "check_round_trip_u128(0x{:x})", x);
+    }
+
+    fn check_order_u128(x: u128, y: u128) {
+        let bufx = encode(x);
+        let bufy = encode(y);
+        assert_eq!(
+            x.cmp(&y),
+            bufx.cmp(&bufy),
+            "check_order_u128(0x{:x}, 0x{:x}) {:?} {:?}",
+            x,
+            y,
+            bufx,
+            bufy,
+        );
+    }
+
+    fn spread(hi: u64, lo: u64) -> u128 {
+        ((hi as u128) << 64) | lo as u128
+    }
+
+    fn interesting_values_u128() -> Vec<u128> {
+        interesting_values()
+            .into_iter()
+            .map(|v| v as u128)
+            .chain(
+                (64..=127)
+                    .filter(|b| !cfg!(miri) || b % 8 == 0)
+                    .map(|b| (1u128 << b) - 2),
+            )
+            .chain(vec![u128::MAX - 1, u128::MAX])
+            .flat_map(|v| vec![v, v.saturating_add(1), v.saturating_add(2)])
+            .collect()
+    }
+
+    #[test]
+    fn test_u64_compatible() {
+        for x in interesting_values() {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(encode(x as u128), buf);
+            assert_eq!(read_bvarint_u128(&buf[..]).unwrap(), x as u128);
+        }
+        let buf = encode(u64::MAX as u128 + 1);
+        assert_eq!(buf[..2], [0xff, 9]);
+        assert!(read_bvarint(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_u64_compatible_quickcheck() {
+        fn check(x: u64) {
+            let mut buf = Vec::new();
+            write_bvarint(x, &mut buf).unwrap();
+            assert_eq!(encode(x as u128), buf);
+            let mut auto = Vec::new();
+            write_bvarint_auto(x as u128, &mut auto).unwrap();
+            assert_eq!(auto, buf);
+            assert_eq!(read_bvarint_auto(&buf[..]).unwrap(), x as u128);
+        }
+        quickcheck(check as fn(u64));
+    }
+
+    #[test]
+    fn test_round_trip_auto_quickcheck() {
+        fn check(hi: u64, lo: u64) {
+            let x = spread(hi, lo);
+            let mut buf = Vec::new();
+            write_bvarint_auto(x, &mut buf).unwrap();
+            assert_eq!(read_bvarint_auto(&buf[..]).unwrap(), x);
+        }
+        quickcheck(check as fn(u64, u64));
+    }
+
+    #[test]
+    fn test_saturating() {
+        let mut max = Vec::new();
+        write_bvarint(u64::MAX, &mut max).unwrap();
+        for &(v, expected) in &[
+            (u64::MAX as u128, false),
+            (u64::MAX as u128 + 1, true),
+            (u128::MAX, true),
+        ] {
+            let mut buf = Vec::new();
+            assert_eq!(
+                write_bvarint_saturating_u128(v, &mut buf).unwrap(),
+                expected
+            );
+            assert_eq!(buf, max);
+        }
+        let mut buf = Vec::new();
+        assert!(!write_bvarint_saturating_u128(5, &mut buf).unwrap());
+        assert_eq!(buf, [5]);
+    }
+
+    #[test]
+    fn test_round_trip_u128_manual() {
+        for x in interesting_values_u128() {
+            check_round_trip_u128(x);
+        }
+    }
+
+    #[test]
+    fn test_order_u128_manual() {
+        let values = interesting_values_u128();
+        for x in &values {
+            for y in &values {
+                check_order_u128(*x, *y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_config() {
+        let mut buf = Vec::new();
+        write_bvarint_u128(0x1234, &mut buf).unwrap();
+        write_bvarint_u128(u128::MAX, &mut buf).unwrap();
+
+        let strict = BvarintConfig::default();
+        let mut r = &buf[..];
+        assert_eq!(strict.read_u128(&mut r).unwrap(), 0x1234);
+        let err = strict.read_u128(&mut r).unwrap_err();
+        assert!(matches!(
+            crate::DecodeError::from(err),
+            crate::DecodeError::ReservedLeadByte { .. }
+        ));
+
+        let wide = BvarintConfig { allow_u128: true };
+        let mut r = &buf[..];
+        assert_eq!(wide.read_u128(&mut r).unwrap(), 0x1234);
+        assert_eq!(wide.read_u128(&mut r).unwrap(), u128::MAX);
+        assert!(r.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_width() {
+        let buf = [0xff, 17, 0, 0];
+        assert!(read_bvarint_u128(&buf[..]).is_err());
+
+        // Second encodings of values that fit in u64.
+        let mut nine = vec![0xff, 9, 0];
+        nine.extend_from_slice(&[0xff; 8]);
+        for buf in [
+            &[0xff, 0x00][..],
+            &[0xff, 0x02, 0x00, 0x05],
+            &[0xff, 8, 1, 0, 0, 0, 0, 0, 0, 0],
+            &nine,
+        ] {
+            let err = read_bvarint_u128(buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{:?}", buf);
+        }
+    }
+
+    fn check_round_trip_bigint(be_bytes: Vec<u8>) {
+        let mut buf = Vec::new();
+        write_bvarint_bytes_bigint(&be_bytes, &mut buf).unwrap();
+        let decoded = read_bvarint_bigint(&buf[..]).unwrap();
+        let start = be_bytes
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(be_bytes.len());
+        assert_eq!(
+            decoded,
+            &be_bytes[start..],
+            "check_round_trip_bigint({:?})",
+            be_bytes
+        );
+    }
+
+    #[test]
+    fn test_bigint_compatible() {
+        for x in interesting_values_u128() {
+            let buf = encode(x);
+            let mut bigint = Vec::new();
+            write_bvarint_bytes_bigint(&x.to_be_bytes(), &mut bigint).unwrap();
+            assert_eq!(bigint, buf);
+            let decoded = read_bvarint_bigint(&buf[..]).unwrap();
+            let mut a = [0; 16];
+            a[(16 - decoded.len())..].copy_from_slice(&decoded);
+            assert_eq!(u128::from_be_bytes(a), x);
+        }
+    }
+
+    #[test]
+    fn test_bigint_manual() {
+        check_round_trip_bigint(Vec::new());
+        check_round_trip_bigint(vec![0, 0, 1]);
+        check_round_trip_bigint(vec![0xff; 300]);
+
+        let mut buf = Vec::new();
+        write_bvarint_bytes_bigint(&[1; 300], &mut buf).unwrap();
+        assert_eq!(buf[..4], [0xff, 0xf8, 0x01, 0x2c]);
+    }
+
+    #[test]
+    fn test_bigint_order() {
+        let values: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0xf6],
+            vec![0xff; 8],
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0xff; 16],
+            vec![1; 17],
+            vec![0xff; 246],
+            vec![1; 247],
+            vec![2; 247],
+        ];
+        let encoded: Vec<Vec<u8>> = values
+            .iter()
+            .map(|v| {
+                let mut buf = Vec::new();
+                write_bvarint_bytes_bigint(v, &mut buf).unwrap();
+                buf
+            })
+            .collect();
+        for pair in encoded.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_bigint_invalid() {
+        let err = read_bvarint_bigint(&[0xff, 8, 1, 0, 0, 0, 0, 0, 0, 0][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut buf = vec![0xff, 9, 0];
+        buf.extend_from_slice(&[1; 8]);
+        let err = read_bvarint_bigint(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = read_bvarint_bigint(&[0xff, 9, 1][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_read_or_overflow() {
+        assert_eq!(
+            read_bvarint_or_overflow(&[0xf7, 0xff][..]).unwrap(),
+            Ok(0xff)
+        );
+
+        let mut buf = Vec::new();
+        let bytes: Vec<u8> = (1..=20).collect();
+        write_bvarint_bytes_bigint(&bytes, &mut buf).unwrap();
+        buf.push(7);
+        let mut r = &buf[..];
+        assert_eq!(read_bvarint_or_overflow(&mut r).unwrap(), Err(bytes));
+        assert_eq!(r, [7]);
+
+        let err = read_bvarint_or_overflow(&[0xff, 20, 1][..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_round_trip_bigint_quickcheck() {
+        quickcheck(check_round_trip_bigint as fn(Vec<u8>));
+    }
+
+    #[test]
+    fn test_round_trip_u128_quickcheck() {
+        fn check(hi: u64, lo: u64) {
+            check_round_trip_u128(spread(hi, lo));
+        }
+        quickcheck(check as fn(u64, u64));
+    }
+
+    #[test]
+    fn test_order_u128_quickcheck() {
+        fn check(hx: u64, lx: u64, hy: u64, ly: u64) {
+            check_order_u128(spread(hx, lx), spread(hy, ly));
+        }
+        quickcheck(check as fn(u64, u64, u64, u64));
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "derive")]
+
+use bvarint::{BvarintDecode, BvarintEncode};
+
+#[derive(BvarintEncode, BvarintDecode, Debug, PartialEq)]
+struct Record {
+    id: u64,
+    len: u32,
+    #[bvarint(signed)]
+    delta: i64,
+    #[bvarint(signed)]
+    small: i16,
+}
+
+#[derive(BvarintEncode, BvarintDecode, Debug, PartialEq)]
+struct Pair(u8, Record);
+
+#[derive(BvarintEncode, BvarintDecode, Debug, PartialEq)]
+struct Empty;
+
+fn encode(v: &impl BvarintEncode) -> Vec<u8> {
+    let mut buf = Vec::new();
+    v.write_bvarint(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_round_trip() {
+    let record = Record {
+        id: 0x1234,
+        len: 7,
+        delta: -2,
+        small: i16::MIN,
+    };
+    let buf = encode(&record);
+    assert_eq!(buf[..5], [0xf8, 0x12, 0x34, 7, 3]);
+    assert_eq!(Record::read_bvarint(&buf[..]).unwrap(), record);
+
+    let pair = Pair(1, record);
+    let buf = encode(&pair);
+    assert_eq!(buf[0], 1);
+    assert_eq!(Pair::read_bvarint(&buf[..]).unwrap(), pair);
+
+    assert!(encode(&Empty).is_empty());
+    assert_eq!(Empty::read_bvarint(&[][..]).unwrap(), Empty);
+}
+
+#[test]
+fn test_out_of_range() {
+    let mut buf = Vec::new();
+    bvarint::write_bvarint(0x1234, &mut buf).unwrap();
+    bvarint::write_bvarint(1 << 40, &mut buf).unwrap();
+    bvarint::write_bvarint_i64(0, &mut buf).unwrap();
+    bvarint::write_bvarint_i64(0, &mut buf).unwrap();
+    let err = Record::read_bvarint(&buf[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let mut buf = vec![1, 7, 0];
+    bvarint::write_bvarint_i64(i16::MAX as i64 + 1, &mut buf).unwrap();
+    let err = Record::read_bvarint(&buf[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,61 @@
+//! Format stability: the lead byte classes, their lengths and value ranges
+//! are compared against a committed table. Changing the encoding breaks
+//! this test on purpose; update the table only for a deliberate format
+//! change.
+
+use bvarint::{encode_to_stack, peek_len, value_range_for_width};
+use std::fmt::Write;
+
+const GOLDEN: &str = "\
+lead 00..=f6 len 1 values 0x0..=0xf6
+lead f7 len 2 values 0xf7..=0xff
+lead f8 len 3 values 0x100..=0xffff
+lead f9 len 4 values 0x10000..=0xffffff
+lead fa len 5 values 0x1000000..=0xffffffff
+lead fb len 6 values 0x100000000..=0xffffffffff
+lead fc len 7 values 0x10000000000..=0xffffffffffff
+lead fd len 8 values 0x1000000000000..=0xffffffffffffff
+lead fe len 9 values 0x100000000000000..=0xffffffffffffffff
+lead ff len 0 reserved
+";
+
+/// Build the table from the public API, checking that every lead byte in a
+/// class agrees on the length, and that the ends of each range encode with
+/// that lead byte.
+fn table() -> String {
+    let mut out = String::new();
+    let mut lead = 0u8;
+    loop {
+        let len = peek_len(lead);
+        let last = if lead <= 0xf6 { 0xf6 } else { lead };
+        for b in lead..=last {
+            assert_eq!(peek_len(b), len, "lead {:02x}", b);
+        }
+        if lead == last {
+            write!(out, "lead {:02x} len {}", lead, len).unwrap();
+        } else {
+            write!(out, "lead {:02x}..={:02x} len {}", lead, last, len).unwrap();
+        }
+        match value_range_for_width(len) {
+            Some(range) => {
+                for v in [*range.start(), *range.end()] {
+                    let (buf, n) = encode_to_stack(v);
+                    assert_eq!(n, len, "value {:#x}", v);
+                    assert!((lead..=last).contains(&buf[0]), "value {:#x}", v);
+                }
+                writeln!(out, " values {:#x}..={:#x}", range.start(), range.end()).unwrap();
+            }
+            None => writeln!(out, " reserved").unwrap(),
+        }
+        if last == 0xff {
+            break;
+        }
+        lead = last + 1;
+    }
+    out
+}
+
+#[test]
+fn golden_format() {
+    assert_eq!(table(), GOLDEN);
+}
@@ -0,0 +1,64 @@
+//! Single-value encode and decode must not allocate. A counting global
+//! allocator, which only applies to this test binary, checks that.
+
+#![cfg(feature = "std")]
+
+use bvarint::{decode_bvarint, encode_bvarint, read_bvarint, write_bvarint, MAX_ENCODED_LEN};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Count allocations made by the current thread, so the test harness's own
+/// threads are not counted.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCS.try_with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocs_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCS.with(Cell::get);
+    f();
+    ALLOCS.with(Cell::get) - before
+}
+
+#[test]
+fn single_values_do_not_allocate() {
+    let values: Vec<u64> = (0..64).flat_map(|s| [1 << s, (1 << s) - 1]).collect();
+    let allocs = allocs_during(|| {
+        for &v in &values {
+            let mut buf = [0u8; MAX_ENCODED_LEN];
+            write_bvarint(v, &mut buf[..]).unwrap();
+            assert_eq!(read_bvarint(&buf[..]).unwrap(), v);
+
+            let mut buf = [0u8; MAX_ENCODED_LEN];
+            let len = encode_bvarint(v, &mut buf).unwrap();
+            assert_eq!(decode_bvarint(&buf).unwrap(), (v, len));
+        }
+    });
+    assert_eq!(allocs, 0);
+}
+
+#[test]
+fn counter_sees_allocations() {
+    let allocs = allocs_during(|| drop(std::hint::black_box(vec![0u8; 16])));
+    assert_eq!(allocs, 1);
+}
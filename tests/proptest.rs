@@ -0,0 +1,60 @@
+//! Round-trip and order properties using proptest, biased towards width
+//! boundaries for better shrinking near transitions.
+
+#![cfg(feature = "std")]
+
+use bvarint::{read_bvarint, write_bvarint, ONE_BYTE_MAX, THREE_BYTE_MAX, TWO_BYTE_MAX};
+use proptest::prelude::*;
+
+/// Largest value of each encoded width.
+fn width_max(index: usize) -> u64 {
+    match index {
+        0 => ONE_BYTE_MAX,
+        1 => TWO_BYTE_MAX,
+        2 => THREE_BYTE_MAX,
+        8 => u64::MAX,
+        n => (1u64 << (8 * n)) - 1,
+    }
+}
+
+fn value() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        any::<u64>(),
+        (0..9usize, -3i64..=3).prop_map(|(i, d)| width_max(i).wrapping_add(d as u64)),
+    ]
+}
+
+fn encode(x: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_bvarint(x, &mut buf).unwrap();
+    buf
+}
+
+/// Miri is too slow for the default number of cases, and its isolation
+/// rejects the file-based failure persistence.
+fn config() -> ProptestConfig {
+    if cfg!(miri) {
+        ProptestConfig {
+            cases: 8,
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        }
+    } else {
+        ProptestConfig::default()
+    }
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn round_trip(x in value()) {
+        let buf = encode(x);
+        prop_assert_eq!(read_bvarint(&buf[..]).unwrap(), x);
+    }
+
+    #[test]
+    fn order(x in value(), y in value()) {
+        prop_assert_eq!(x.cmp(&y), encode(x).cmp(&encode(y)));
+    }
+}
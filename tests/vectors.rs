@@ -0,0 +1,48 @@
+//! Format stability: every value in `vectors.txt` must encode to exactly
+//! the stored bytes, and decode back from them.
+
+use bvarint::{decode_bvarint, encode_to_stack, MAX_ENCODED_LEN};
+
+fn parse_hex(s: &str) -> Vec<u8> {
+    assert!(s.len().is_multiple_of(2), "odd hex length: {}", s);
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn vectors() -> Vec<(u64, Vec<u8>)> {
+    include_str!("vectors.txt")
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let value = fields.next().unwrap().parse().unwrap();
+            let bytes = parse_hex(fields.next().unwrap());
+            assert!(fields.next().is_none(), "extra fields: {}", line);
+            (value, bytes)
+        })
+        .collect()
+}
+
+#[test]
+fn golden_vectors() {
+    let vectors = vectors();
+    for (value, bytes) in &vectors {
+        let (buf, len) = encode_to_stack(*value);
+        assert_eq!(&buf[..len], &bytes[..], "encoding of {}", value);
+        assert_eq!(
+            decode_bvarint(bytes).unwrap(),
+            (*value, bytes.len()),
+            "decoding of {}",
+            value
+        );
+    }
+    for width in 1..=MAX_ENCODED_LEN {
+        assert!(
+            vectors.iter().any(|(_, bytes)| bytes.len() == width),
+            "no vector of {} bytes",
+            width
+        );
+    }
+}